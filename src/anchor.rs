@@ -0,0 +1,105 @@
+use crate::mouse::{cursor_world_position, BuiltItem};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+const ANCHOR_GRAB_RADIUS: f32 = 12.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnchorMode {
+    #[default]
+    TopCentre,
+    BottomLeft,
+    Custom,
+}
+
+impl AnchorMode {
+    pub const ALL: [AnchorMode; 3] = [
+        AnchorMode::TopCentre,
+        AnchorMode::BottomLeft,
+        AnchorMode::Custom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnchorMode::TopCentre => "Top centre",
+            AnchorMode::BottomLeft => "Bottom left",
+            AnchorMode::Custom => "Custom",
+        }
+    }
+}
+
+/// Point that exported coordinates are relative to.
+#[derive(Resource, Default)]
+pub struct Anchor {
+    pub mode: AnchorMode,
+    pub custom: Vec2,
+    pub dragging: bool,
+}
+
+impl Anchor {
+    /// Bounding box modes use the centres of the items, since those are the coordinates the game reads.
+    pub fn point(&self, positions: impl Iterator<Item = Vec2>) -> Vec2 {
+        let mut min = Vec2::splat(f32::MAX);
+        let mut max = Vec2::splat(f32::MIN);
+        if self.mode != AnchorMode::Custom {
+            for pos in positions {
+                min = min.min(pos);
+                max = max.max(pos);
+            }
+        }
+        match self.mode {
+            AnchorMode::Custom => self.custom,
+            _ if min.x > max.x => Vec2::ZERO,
+            AnchorMode::TopCentre => Vec2::new((min.x + max.x) / 2., max.y),
+            AnchorMode::BottomLeft => min,
+        }
+    }
+
+    pub fn grabs(&self, position: Vec2) -> bool {
+        self.mode == AnchorMode::Custom && self.custom.distance(position) <= ANCHOR_GRAB_RADIUS
+    }
+}
+
+pub struct AnchorPlugin;
+
+impl Plugin for AnchorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Anchor>()
+            .add_systems(Update, (drag_anchor, draw_anchor));
+    }
+}
+
+fn drag_anchor(
+    mut anchor: ResMut<Anchor>,
+    buttons: Res<Input<MouseButton>>,
+    windows_q: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<&Transform, With<Camera>>,
+) {
+    let Some(position) = cursor_world_position(windows_q.single(), camera_q.single()) else {return};
+    if buttons.just_pressed(MouseButton::Left) && anchor.grabs(position) {
+        anchor.dragging = true;
+    }
+    if !buttons.pressed(MouseButton::Left) {
+        anchor.dragging = false;
+    }
+    if anchor.dragging {
+        anchor.custom = position.round();
+    }
+}
+
+fn draw_anchor(
+    anchor: Res<Anchor>,
+    item_q: Query<&Transform, With<BuiltItem>>,
+    mut gizmos: Gizmos,
+) {
+    let point = anchor.point(item_q.iter().map(|t| t.translation.truncate()));
+    let color = if anchor.mode == AnchorMode::Custom {
+        Color::rgb(1., 0.2, 0.4)
+    } else {
+        Color::rgb(0.2, 0.2, 0.6)
+    };
+    gizmos.line_2d(point - Vec2::X * 10., point + Vec2::X * 10., color);
+    gizmos.line_2d(point - Vec2::Y * 10., point + Vec2::Y * 10., color);
+    gizmos.circle_2d(point, ANCHOR_GRAB_RADIUS / 2., color);
+}
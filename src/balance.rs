@@ -0,0 +1,409 @@
+use crate::{
+    catalog::{self, OBJECTS},
+    scoring::StructureMeta,
+    simulate::{read_structures, SpawnSource, STRUCTURES_DIR},
+    structure::write_weight,
+    UiState,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Normalize {
+    /// Every weight is multiplied by the same number so they add up to the target.
+    Rescale,
+    /// Pinned structures get their share, the rest share what is left the way they did before.
+    Pin,
+}
+
+#[derive(Resource)]
+struct Balancing {
+    structures: Vec<SpawnSource>,
+    errors: Vec<String>,
+    /// Counts the objects spawning on their own into the shares.
+    with_singles: bool,
+    mode: Normalize,
+    target_total: f64,
+    /// Target share in percent of the pinned structures, by their position in `structures`.
+    pins: Vec<Option<f64>>,
+    confirming: bool,
+    status: String,
+    loaded: bool,
+}
+
+impl Default for Balancing {
+    fn default() -> Self {
+        Balancing {
+            structures: Vec::new(),
+            errors: Vec::new(),
+            with_singles: true,
+            mode: Normalize::Rescale,
+            target_total: 1000.,
+            pins: Vec::new(),
+            confirming: false,
+            status: String::new(),
+            loaded: false,
+        }
+    }
+}
+
+impl Balancing {
+    fn refresh(&mut self) {
+        (self.structures, self.errors) = read_structures();
+        self.pins = vec![None; self.structures.len()];
+        self.confirming = false;
+        self.loaded = true;
+    }
+
+    /// Structures first, then the single objects if they are counted.
+    fn sources(&self) -> Vec<(String, f64, Vec<(usize, Vec2)>)> {
+        let singles = OBJECTS.iter().enumerate().filter_map(|(index, object)| {
+            let weight = catalog::spawn_weight_of(index);
+            (self.with_singles && weight > 0.)
+                .then(|| (object.name.to_string(), weight, vec![(index, Vec2::ZERO)]))
+        });
+        self.structures
+            .iter()
+            .map(|s| (s.name.clone(), s.weight, s.items.clone()))
+            .chain(singles)
+            .collect()
+    }
+
+    /// New weights of the structures, or why they can't be computed.
+    fn normalized(&self) -> Result<Vec<f64>, String> {
+        let weights: Vec<f64> = self.structures.iter().map(|s| s.weight).collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0. {
+            return Err("The structures have no weight to scale".to_string());
+        }
+        let new: Vec<f64> = match self.mode {
+            Normalize::Rescale => weights
+                .iter()
+                .map(|w| w * self.target_total / total)
+                .collect(),
+            Normalize::Pin => {
+                let pinned: f64 = self.pins.iter().flatten().sum();
+                if pinned > 100. {
+                    return Err(format!("The pinned shares add up to {:.1}%", pinned));
+                }
+                let free: f64 = weights
+                    .iter()
+                    .zip(self.pins.iter())
+                    .filter(|(_, pin)| pin.is_none())
+                    .map(|(w, _)| w)
+                    .sum();
+                if free <= 0. && pinned < 100. {
+                    return Err("Pin less than 100% or leave something unpinned".to_string());
+                }
+                weights
+                    .iter()
+                    .zip(self.pins.iter())
+                    .map(|(w, pin)| match pin {
+                        Some(share) => share / 100. * self.target_total,
+                        None => w / free * (100. - pinned) / 100. * self.target_total,
+                    })
+                    .collect()
+            }
+        };
+        let new: Vec<f64> = new.into_iter().map(round_weight).collect();
+        for (i, weight) in new.iter().enumerate() {
+            // A weight of 0 keeps the game from ever spawning the structure.
+            if weights[i] > 0. && *weight <= 0. && self.pins[i].is_none() {
+                return Err(format!(
+                    "{} would get a weight of 0 and never spawn",
+                    self.structures[i].name
+                ));
+            }
+        }
+        // A pinned share or the rounding must not move a structure past or onto others it was lighter or heavier than.
+        for i in 0..new.len() {
+            for j in i + 1..new.len() {
+                let before = weights[i] - weights[j];
+                let after = new[i] - new[j];
+                if before * after < 0. || (before != 0. && after == 0.) {
+                    return Err(format!(
+                        "{} and {} would swap places or end up equal, change the pinned shares or the total",
+                        self.structures[i].name, self.structures[j].name
+                    ));
+                }
+            }
+        }
+        Ok(new)
+    }
+}
+
+/// Significant digits kept in a weight, plenty to tell weights apart while keeping the files readable.
+const WEIGHT_DIGITS: i32 = 6;
+
+/// Rounds to significant digits rather than decimals, so small weights don't become 0.
+fn round_weight(weight: f64) -> f64 {
+    if weight <= 0. || !weight.is_finite() {
+        return weight;
+    }
+    let decimals = WEIGHT_DIGITS - 1 - weight.log10().floor() as i32;
+    // Dividing by a whole power of ten is exact, multiplying by a fraction of one is not.
+    if decimals >= 0 {
+        let scale = 10f64.powi(decimals);
+        (weight * scale).round() / scale
+    } else {
+        let scale = 10f64.powi(-decimals);
+        (weight / scale).round() * scale
+    }
+}
+
+/// Writes the weight into the structure and into its meta file, if it was exported with one.
+fn write_new_weight(name: &str, weight: &str) -> Result<(), String> {
+    let path = Path::new(STRUCTURES_DIR).join(name);
+    write_weight(&path, weight)?;
+    match StructureMeta::read_for(&path) {
+        Ok(mut meta) => {
+            meta.weight = weight.to_string();
+            meta.write(name)
+        }
+        Err(_) => Ok(()),
+    }
+}
+
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Balancing>()
+            .add_systems(Update, balancing_window);
+    }
+}
+
+fn balancing_window(
+    mut balancing: ResMut<Balancing>,
+    mut ui_state: ResMut<UiState>,
+    mut contexts: EguiContexts,
+) {
+    let balancing = &mut *balancing;
+    egui::Window::new("Balancing")
+        .default_pos(egui::pos2(660., 150.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            // Only read once somebody opens the window.
+            if !balancing.loaded {
+                balancing.refresh();
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Reload").clicked() {
+                    balancing.refresh();
+                }
+                ui.checkbox(
+                    &mut balancing.with_singles,
+                    "Count objects spawning on their own",
+                );
+            });
+            for error in balancing.errors.iter() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            let sources = balancing.sources();
+            let total: f64 = sources.iter().map(|(_, w, _)| w).sum();
+            ui.label(format!(
+                "{} structures, total weight {}",
+                balancing.structures.len(),
+                round_weight(total)
+            ));
+            let mut per_thousand = vec![0.; OBJECTS.len()];
+            for (_, weight, items) in sources.iter() {
+                for (index, _) in items.iter() {
+                    per_thousand[*index] += weight / total.max(f64::EPSILON) * 1000.;
+                }
+            }
+            ui.collapsing("Objects per 1000 spawns", |ui| {
+                for (index, count) in per_thousand.iter().enumerate() {
+                    ui.label(format!("{}: {:.1}", catalog::name_of(index), count));
+                }
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut balancing.mode, Normalize::Rescale, "Rescale");
+                ui.selectable_value(&mut balancing.mode, Normalize::Pin, "Pin shares");
+                ui.label("Total of the structures:");
+                ui.add(
+                    egui::DragValue::new(&mut balancing.target_total)
+                        .clamp_range(0.001..=1_000_000.),
+                );
+            });
+            let normalized = balancing.normalized();
+            let pinning = balancing.mode == Normalize::Pin;
+            let structures_total: f64 = balancing.structures.iter().map(|s| s.weight).sum();
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    egui::Grid::new("balancing").striped(true).show(ui, |ui| {
+                        ui.label("Structure");
+                        ui.label("Weight");
+                        ui.label("Share");
+                        ui.label("New weight");
+                        if pinning {
+                            ui.label("Pinned % of the structures");
+                        }
+                        ui.end_row();
+                        for (i, (name, weight, _)) in sources.iter().enumerate() {
+                            ui.label(name);
+                            ui.label(weight.to_string());
+                            ui.label(format!("{:.2}%", weight / total.max(f64::EPSILON) * 100.));
+                            // Single objects are only counted, their weights live in the game.
+                            let Some(pin) = balancing.pins.get_mut(i) else {
+                                ui.end_row();
+                                continue;
+                            };
+                            match &normalized {
+                                Ok(new) => ui.label(new[i].to_string()),
+                                Err(_) => ui.label("-"),
+                            };
+                            if pinning {
+                                let mut pinned = pin.is_some();
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut pinned, "");
+                                    match (pinned, pin.as_mut()) {
+                                        (true, Some(share)) => {
+                                            ui.add(
+                                                egui::DragValue::new(share)
+                                                    .speed(0.1)
+                                                    .clamp_range(0..=100),
+                                            );
+                                        }
+                                        (true, None) => {
+                                            *pin = Some(
+                                                weight / structures_total.max(f64::EPSILON) * 100.,
+                                            )
+                                        }
+                                        (false, _) => *pin = None,
+                                    }
+                                });
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            let new = match normalized {
+                Ok(new) => new,
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                    return;
+                }
+            };
+            let changed: Vec<usize> = (0..new.len())
+                .filter(|i| new[*i] != balancing.structures[*i].weight)
+                .collect();
+            if !balancing.confirming {
+                if ui
+                    .add_enabled(
+                        !changed.is_empty(),
+                        egui::Button::new(format!("Write {} files", changed.len())),
+                    )
+                    .clicked()
+                {
+                    balancing.confirming = true;
+                }
+            } else {
+                ui.label(format!(
+                    "Change the weight of {} structures in the structures folder?",
+                    changed.len()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Write").clicked() {
+                        let mut failed = Vec::new();
+                        for i in changed.iter() {
+                            let name = &balancing.structures[*i].name;
+                            let weight = new[*i].to_string();
+                            match write_new_weight(name, &weight) {
+                                // Exporting the open structure again shouldn't undo the change.
+                                Ok(()) if &ui_state.name == name => ui_state.weight_s = weight,
+                                Ok(()) => {}
+                                Err(e) => failed.push(format!("{}: {}", name, e)),
+                            }
+                        }
+                        balancing.refresh();
+                        balancing.status = if failed.is_empty() {
+                            format!("Wrote {} files", changed.len())
+                        } else {
+                            format!("Writing failed for {}", failed.join(", "))
+                        };
+                    }
+                    if ui.button("Cancel").clicked() {
+                        balancing.confirming = false;
+                    }
+                });
+            }
+            if !balancing.status.is_empty() {
+                ui.label(&balancing.status);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balancing(weights: &[f64]) -> Balancing {
+        let structures: Vec<SpawnSource> = weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| SpawnSource {
+                name: format!("s{}", i),
+                weight: *weight,
+                items: Vec::new(),
+                single: false,
+            })
+            .collect();
+        Balancing {
+            pins: vec![None; structures.len()],
+            structures,
+            loaded: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rescaling_keeps_the_order() {
+        let weights = [0.0001, 1., 1.0004, 3., 119., 250.5, 0.2];
+        for target in [0.001, 1., 1000., 123_456.] {
+            let mut balancing = balancing(&weights);
+            balancing.target_total = target;
+            let new = balancing.normalized().unwrap();
+            for i in 0..weights.len() {
+                assert!(new[i] > 0., "{} lost its weight", i);
+                for j in 0..weights.len() {
+                    if weights[i] < weights[j] {
+                        assert!(new[i] < new[j], "{} and {} swapped or merged", i, j);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rounding_that_merges_weights_is_refused() {
+        let balancing = balancing(&[1., 1.000_000_1]);
+        assert!(balancing.normalized().is_err());
+    }
+
+    #[test]
+    fn weights_keep_their_significant_digits() {
+        assert_eq!(round_weight(0.000_123_456_78), 0.000_123_457);
+        assert_eq!(round_weight(2.5), 2.5);
+        assert_eq!(round_weight(123_456_789.), 123_457_000.);
+        assert_eq!(round_weight(0.), 0.);
+    }
+
+    #[test]
+    fn pins_that_reorder_are_refused() {
+        let mut balancing = balancing(&[10., 20., 70.]);
+        balancing.mode = Normalize::Pin;
+        // The lightest structure would end up the heaviest.
+        balancing.pins[0] = Some(50.);
+        assert!(balancing.normalized().is_err());
+        balancing.pins[0] = Some(15.);
+        assert!(balancing.normalized().is_ok());
+    }
+}
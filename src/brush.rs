@@ -0,0 +1,244 @@
+use crate::{
+    anchor::Anchor,
+    grid::{snap_position, GridSettings, Guides},
+    mouse::{spawn_built_item, BuiltItem, DrawOrder, EditorTool, ItemPlaced},
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
+    CombinedSheet, GameState,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rand::Rng;
+use std::f32::consts::TAU;
+
+/// Density is counted per this many square pixels, a 100x100 area.
+const DENSITY_AREA: f32 = 10_000.;
+/// Random spots tried each frame when scattering.
+const SCATTER_ATTEMPTS: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BrushMode {
+    /// One object per click.
+    Single,
+    /// Objects along the stroke.
+    Paint,
+    /// Random objects around the cursor until the area is dense enough.
+    Scatter,
+}
+
+#[derive(Resource)]
+pub struct BrushSettings {
+    pub mode: BrushMode,
+    /// Minimum distance between painted or scattered objects.
+    pub spacing: f32,
+    /// Largest random offset added to every painted object.
+    pub jitter: f32,
+    pub radius: f32,
+    /// Objects per 100x100 pixels.
+    pub density: f32,
+}
+
+impl Default for BrushSettings {
+    fn default() -> Self {
+        BrushSettings {
+            mode: BrushMode::Single,
+            spacing: 120.,
+            jitter: 0.,
+            radius: 250.,
+            density: 0.3,
+        }
+    }
+}
+
+pub struct BrushPlugin;
+
+impl Plugin for BrushPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BrushSettings>().add_systems(
+            Update,
+            (brush_window, paint, scatter).run_if(in_state(GameState::Building)),
+        );
+    }
+}
+
+fn random_offset(rng: &mut impl Rng, radius: f32) -> Vec2 {
+    if radius <= 0. {
+        return Vec2::ZERO;
+    }
+    // The square root keeps the points evenly spread instead of crowding the middle.
+    Vec2::from_angle(rng.gen_range(0. ..TAU)) * radius * rng.gen::<f32>().sqrt()
+}
+
+fn paint(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    editor_tool_q: Query<(&TextureAtlasSprite, &Transform), With<EditorTool>>,
+    order_q: Query<&DrawOrder>,
+    buttons: Res<Input<MouseButton>>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    anchor: Res<Anchor>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    settings: Res<BrushSettings>,
+    mut placed: EventWriter<ItemPlaced>,
+    // Last position of the cursor and of the last painted object in the current stroke.
+    mut stroke: Local<Option<(Vec2, Vec2)>>,
+) {
+    let Ok((sprite, tool)) = editor_tool_q.get_single() else {
+        return;
+    };
+    if settings.mode != BrushMode::Paint || !buttons.pressed(MouseButton::Left) {
+        *stroke = None;
+        return;
+    }
+    let cursor = tool.translation.truncate();
+    let mut rng = rand::thread_rng();
+    let mut order = DrawOrder::above(order_q.iter());
+    let mut place = |commands: &mut Commands, position: Vec2| {
+        let position = snap_position(&grid, &guides, position);
+        let jittered = position + random_offset(&mut rng, settings.jitter);
+        let entity = spawn_built_item(commands, &texture_atlas.0, sprite.index, jittered, order);
+        placed.send(ItemPlaced {
+            entity,
+            index: sprite.index,
+            position: jittered,
+            order,
+        });
+        order.0 += 1;
+        position
+    };
+
+    let Some((last_cursor, mut last_placed)) = *stroke else {
+        if buttons.just_pressed(MouseButton::Left)
+            && !overlaps_ui(tool, &ui_q)
+            && !over_egui.0
+            && !anchor.dragging
+            && !anchor.grabs(cursor)
+        {
+            let first = place(&mut commands, cursor);
+            *stroke = Some((cursor, first));
+        }
+        return;
+    };
+    // Walks the way the cursor moved this frame, so fast strokes don't leave gaps.
+    let spacing = settings.spacing.max(1.);
+    let steps = (last_cursor.distance(cursor) / (spacing / 4.)).ceil() as usize;
+    for i in 1..=steps {
+        let point = last_cursor.lerp(cursor, i as f32 / steps as f32);
+        if point.distance(last_placed) >= spacing {
+            last_placed = place(&mut commands, point);
+        }
+    }
+    *stroke = Some((cursor, last_placed));
+}
+
+fn scatter(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    editor_tool_q: Query<(&TextureAtlasSprite, &Transform), With<EditorTool>>,
+    item_q: Query<(&Transform, &DrawOrder), With<BuiltItem>>,
+    buttons: Res<Input<MouseButton>>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    anchor: Res<Anchor>,
+    over_egui: Res<PointerOverEgui>,
+    settings: Res<BrushSettings>,
+    mut placed: EventWriter<ItemPlaced>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((sprite, tool)) = editor_tool_q.get_single() else {
+        return;
+    };
+    if settings.mode != BrushMode::Scatter {
+        return;
+    }
+    let cursor = tool.translation.truncate();
+    gizmos.circle_2d(cursor, settings.radius, Color::rgb(1., 0.5, 0.));
+    if !buttons.pressed(MouseButton::Left)
+        || overlaps_ui(tool, &ui_q)
+        || over_egui.0
+        || anchor.dragging
+        || anchor.grabs(cursor)
+    {
+        return;
+    }
+
+    let mut nearby: Vec<Vec2> = item_q
+        .iter()
+        .map(|(trans, _)| trans.translation.truncate())
+        .filter(|position| position.distance(cursor) <= settings.radius + settings.spacing)
+        .collect();
+    let inside = nearby
+        .iter()
+        .filter(|position| position.distance(cursor) <= settings.radius)
+        .count();
+    let area = std::f32::consts::PI * settings.radius * settings.radius;
+    let target = (settings.density * area / DENSITY_AREA).round() as usize;
+    if inside >= target {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..SCATTER_ATTEMPTS {
+        let position = cursor + random_offset(&mut rng, settings.radius);
+        if nearby
+            .iter()
+            .all(|other| other.distance(position) >= settings.spacing)
+        {
+            let order = DrawOrder::above(item_q.iter().map(|(_, order)| order));
+            let entity = spawn_built_item(
+                &mut commands,
+                &texture_atlas.0,
+                sprite.index,
+                position,
+                order,
+            );
+            placed.send(ItemPlaced {
+                entity,
+                index: sprite.index,
+                position,
+                order,
+            });
+            nearby.push(position);
+            break;
+        }
+    }
+}
+
+fn brush_window(mut settings: ResMut<BrushSettings>, mut contexts: EguiContexts) {
+    egui::Window::new("Brush")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.mode, BrushMode::Single, "Single");
+                ui.selectable_value(&mut settings.mode, BrushMode::Paint, "Paint");
+                ui.selectable_value(&mut settings.mode, BrushMode::Scatter, "Scatter");
+            });
+            if settings.mode == BrushMode::Single {
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Minimum spacing:");
+                ui.add(egui::DragValue::new(&mut settings.spacing).clamp_range(1..=1000));
+            });
+            if settings.mode == BrushMode::Paint {
+                ui.horizontal(|ui| {
+                    ui.label("Jitter:");
+                    ui.add(egui::DragValue::new(&mut settings.jitter).clamp_range(0..=500));
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label("Radius:");
+                    ui.add(egui::DragValue::new(&mut settings.radius).clamp_range(10..=1000));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Objects per 100x100:");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.density)
+                            .speed(0.01)
+                            .clamp_range(0.01..=5.),
+                    );
+                });
+            }
+        });
+}
@@ -0,0 +1,68 @@
+use crate::{layers::Layers, mouse::BuiltItem};
+use bevy::prelude::*;
+
+/// Goes up whenever placed objects are added, removed, moved, changed or hidden, or hidden objects start or stop being exported.
+#[derive(Resource, Default)]
+pub struct StructureVersion(pub u64);
+
+/// Result of analysing the structure, worked out again only once the key it was made from changes.
+pub struct Cached<K, V> {
+    entry: Option<(K, V)>,
+}
+
+impl<K, V> Default for Cached<K, V> {
+    fn default() -> Self {
+        Cached { entry: None }
+    }
+}
+
+impl<K: PartialEq, V> Cached<K, V> {
+    pub fn get(&mut self, key: K, compute: impl FnOnce() -> V) -> &V {
+        if self.fresh(&key).is_none() {
+            self.entry = Some((key, compute()));
+        }
+        &self.entry.as_ref().unwrap().1
+    }
+
+    /// The result for this key, if it was already worked out.
+    pub fn fresh(&self, key: &K) -> Option<&V> {
+        self.entry
+            .as_ref()
+            .filter(|(cached, _)| cached == key)
+            .map(|(_, value)| value)
+    }
+}
+
+pub struct CachePlugin;
+
+impl Plugin for CachePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StructureVersion>()
+            .add_systems(PostUpdate, bump_structure_version);
+    }
+}
+
+fn bump_structure_version(
+    added: Query<(), Added<BuiltItem>>,
+    changed: Query<
+        (),
+        (
+            With<BuiltItem>,
+            Or<(
+                Changed<Transform>,
+                Changed<TextureAtlasSprite>,
+                Changed<Visibility>,
+            )>,
+        ),
+    >,
+    mut removed: RemovedComponents<BuiltItem>,
+    layers: Res<Layers>,
+    mut export_hidden: Local<bool>,
+    mut version: ResMut<StructureVersion>,
+) {
+    let hidden_changed = *export_hidden != layers.export_hidden;
+    *export_hidden = layers.export_hidden;
+    if !added.is_empty() || !changed.is_empty() || removed.iter().count() > 0 || hidden_changed {
+        version.0 += 1;
+    }
+}
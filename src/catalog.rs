@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ObjectRole {
+    /// Hurts the player on contact.
+    Hazard,
+    /// Collected by the player.
+    Pickup,
+    /// Invisible in the game, releases a plane once the player touches it.
+    Sensor,
+}
+
+/// Pull of an object on the player.
+#[derive(Clone, Copy)]
+pub struct Gravity {
+    /// Distance from the centre where the pull ends.
+    pub radius: f32,
+    /// Pull right at the centre, fading out towards the radius.
+    pub strength: f32,
+}
+
+impl Gravity {
+    pub fn pull_at(&self, distance: f32) -> f32 {
+        (self.strength * (1. - distance / self.radius)).max(0.)
+    }
+}
+
+pub struct ObjectInfo {
+    pub name: &'static str,
+    /// Size of the visible sprite inside its combined_sheet.png cell, the cells themselves are mostly padding.
+    /// Measured around the pixels that are at least half opaque, the test below checks it against the sheet.
+    pub size: Vec2,
+    pub role: ObjectRole,
+    pub gravity: Option<Gravity>,
+    /// How much collecting the object helps the player.
+    pub reward: f32,
+    /// Weight the game spawns the object with on its own, next to the structures. 0 where it isn't known.
+    pub spawn_weight: f64,
+}
+
+/// Size of one cell of combined_sheet.png, which has one cell per object and an unused last one.
+pub const SHEET_CELL: Vec2 = Vec2::new(2254. / 7., 223.);
+pub const SHEET_COLUMNS: usize = 7;
+
+/// Every placeable object, indexed the same way as combined_sheet.png.
+pub const OBJECTS: [ObjectInfo; 6] = [
+    ObjectInfo {
+        name: "blackhole",
+        size: Vec2::new(100., 100.),
+        role: ObjectRole::Hazard,
+        gravity: Some(Gravity {
+            radius: 300.,
+            strength: 1.,
+        }),
+        reward: 0.,
+        spawn_weight: 0.,
+    },
+    ObjectInfo {
+        name: "rainbow",
+        size: Vec2::new(100., 50.),
+        role: ObjectRole::Pickup,
+        gravity: None,
+        reward: 2.,
+        spawn_weight: 0.2,
+    },
+    ObjectInfo {
+        name: "energybar",
+        size: Vec2::new(146., 70.),
+        role: ObjectRole::Pickup,
+        gravity: None,
+        reward: 1.,
+        spawn_weight: 12.,
+    },
+    ObjectInfo {
+        name: "regular",
+        size: Vec2::new(100., 100.),
+        role: ObjectRole::Hazard,
+        gravity: None,
+        reward: 0.,
+        spawn_weight: 119.,
+    },
+    ObjectInfo {
+        name: "plane",
+        size: Vec2::new(192., 64.),
+        role: ObjectRole::Sensor,
+        gravity: None,
+        reward: 0.,
+        spawn_weight: 0.,
+    },
+    ObjectInfo {
+        name: "planet",
+        size: Vec2::new(94., 94.),
+        role: ObjectRole::Hazard,
+        gravity: Some(Gravity {
+            radius: 200.,
+            strength: 0.5,
+        }),
+        reward: 0.,
+        spawn_weight: 0.,
+    },
+];
+
+pub fn index_of(name: &str) -> Option<usize> {
+    OBJECTS.iter().position(|object| object.name == name)
+}
+
+pub fn name_of(index: usize) -> &'static str {
+    OBJECTS.get(index).map_or("", |object| object.name)
+}
+
+pub fn size_of(index: usize) -> Vec2 {
+    OBJECTS.get(index).map_or(Vec2::ZERO, |object| object.size)
+}
+
+pub fn role_of(index: usize) -> ObjectRole {
+    OBJECTS
+        .get(index)
+        .map_or(ObjectRole::Pickup, |object| object.role)
+}
+
+pub fn gravity_of(index: usize) -> Option<Gravity> {
+    OBJECTS.get(index).and_then(|object| object.gravity)
+}
+
+pub fn reward_of(index: usize) -> f32 {
+    OBJECTS.get(index).map_or(0., |object| object.reward)
+}
+
+pub fn spawn_weight_of(index: usize) -> f64 {
+    OBJECTS.get(index).map_or(0., |object| object.spawn_weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn sizes_match_the_sheet() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/combined_sheet.png");
+        let sheet = image::open(path).unwrap().to_rgba8();
+        let atlas =
+            TextureAtlas::from_grid(Handle::default(), SHEET_CELL, SHEET_COLUMNS, 1, None, None);
+        for (object, cell) in OBJECTS.iter().zip(atlas.textures.iter()) {
+            let mut min = UVec2::MAX;
+            let mut max = UVec2::ZERO;
+            for y in cell.min.y as u32..cell.max.y as u32 {
+                for x in cell.min.x as u32..cell.max.x as u32 {
+                    if sheet.get_pixel(x, y)[3] >= 128 {
+                        min = min.min(UVec2::new(x, y));
+                        max = max.max(UVec2::new(x + 1, y + 1));
+                    }
+                }
+            }
+            let measured = (max - min).as_vec2();
+            // A pixel of slack for the soft edges.
+            assert!(
+                (measured - object.size).abs().max_element() <= 1.,
+                "{} is {} in the sheet, not {}",
+                object.name,
+                measured,
+                object.size
+            );
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    catalog,
+    lint::{lint, LintRules, LINT_FILE},
+    passability::{self, Player},
+    play::PlaySettings,
+    project::{Project, PROJECT_EXTENSION},
+    scoring::StructureMeta,
+    structure::StructureFile,
+};
+use bevy::prelude::Vec2;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "usage: pupik_editor --check|--lint [--hitbox=WIDTHxHEIGHT] [--speed=PIXELS_PER_SECOND] [--fall=PIXELS_PER_SECOND] [FILE_OR_FOLDER...]";
+
+/// Objects of an exported structure or a saved project as sprite indices and positions in the editor,
+/// where the play column is centred at x 0.
+pub fn read_items(path: &Path) -> Result<Vec<(usize, Vec2)>, String> {
+    let (items, origin): (Vec<(String, f32, f32)>, Vec2) =
+        if path.extension().is_some_and(|e| e == PROJECT_EXTENSION) {
+            let items = Project::read(path)?
+                .items
+                .into_iter()
+                .map(|item| (item.name, item.x, item.y))
+                .collect();
+            (items, Vec2::ZERO)
+        } else {
+            let items = StructureFile::read(path)?
+                .items
+                .into_iter()
+                .map(|item| (item.name, item.x, item.y))
+                .collect();
+            // Exported coordinates are relative to the anchor, which only the export's meta file knows.
+            // Older exports don't have it and are read as they are, like opening them in the editor does.
+            let origin = StructureMeta::read_for(path)
+                .ok()
+                .and_then(|meta| meta.origin())
+                .unwrap_or(Vec2::ZERO);
+            (items, origin)
+        };
+    Ok(items
+        .into_iter()
+        .filter_map(|(name, x, y)| Some((catalog::index_of(&name)?, Vec2::new(x, y) + origin)))
+        .collect())
+}
+
+/// Files given on the command line, folders are replaced by the files inside them.
+fn expand_paths(paths: &[&String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            let mut inside: Vec<PathBuf> = std::fs::read_dir(&path)
+                .map(|dir| {
+                    dir.filter_map(|entry| entry.ok().map(|e| e.path()))
+                        .filter(|p| p.is_file())
+                        .collect()
+                })
+                .unwrap_or_default();
+            inside.sort();
+            files.extend(inside);
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// The editor is built without a console on Windows, so the report goes to the one it was started from.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Fails when started without a console, then only the exit code tells the result.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Runs a command given on the command line without opening the editor. Returns the exit code, or None to start the editor.
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first().map(String::as_str);
+    if command != Some("--check") && command != Some("--lint") {
+        return None;
+    }
+    attach_console();
+    let defaults = PlaySettings::default();
+    let mut player = Player::from(&defaults);
+    let mut paths = Vec::new();
+    for arg in args.iter().skip(1) {
+        let parsed = if let Some(value) = arg.strip_prefix("--hitbox=") {
+            value.split_once('x').and_then(|(w, h)| {
+                player.hitbox = Vec2::new(w.parse().ok()?, h.parse().ok()?);
+                Some(())
+            })
+        } else if let Some(value) = arg.strip_prefix("--speed=") {
+            value.parse().ok().map(|speed| player.speed = speed)
+        } else if let Some(value) = arg.strip_prefix("--fall=") {
+            value.parse().ok().map(|fall| player.fall_speed = fall)
+        } else if arg.starts_with("--") {
+            None
+        } else {
+            paths.push(arg);
+            Some(())
+        };
+        if parsed.is_none() {
+            eprintln!("invalid argument \"{}\"\n{}", arg, USAGE);
+            return Some(2);
+        }
+    }
+    let default_folder = "./structures".to_string();
+    if paths.is_empty() {
+        paths.push(&default_folder);
+    }
+
+    // The same rules the editor uses, set in its "Problems" window.
+    let rules = LintRules::load();
+    if command == Some("--lint") && Path::new(LINT_FILE).exists() {
+        println!("using the rules in {}", LINT_FILE);
+    }
+    let mut failed = false;
+    for path in expand_paths(&paths) {
+        let items = match read_items(&path) {
+            Ok(items) => items,
+            Err(e) => {
+                println!("{}: can't be read, {}", path.display(), e);
+                failed = true;
+                continue;
+            }
+        };
+        if command == Some("--lint") {
+            let problems = lint(&items, &rules, player);
+            println!("{}: {} problems", path.display(), problems.len());
+            for problem in problems.iter() {
+                match problem.at {
+                    Some(at) => println!(
+                        "    x {:.0}, y {:.0}: [{}] {}",
+                        at.x,
+                        at.y,
+                        problem.rule.label(),
+                        problem.message
+                    ),
+                    None => println!("    [{}] {}", problem.rule.label(), problem.message),
+                }
+            }
+            failed |= !problems.is_empty();
+            continue;
+        }
+        let analysis = passability::analyse(&items, player);
+        println!(
+            "{}: {}",
+            path.display(),
+            if analysis.passable {
+                "passable"
+            } else {
+                "NOT PASSABLE"
+            }
+        );
+        for choke in analysis.chokes.iter() {
+            println!(
+                "    y {:.0}, x {:.0} to {:.0}: {}",
+                choke.y,
+                choke.from,
+                choke.to,
+                choke.reason.label()
+            );
+        }
+        failed |= !analysis.passable;
+    }
+    Some(if failed { 1 } else { 0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ProjectItem;
+
+    /// An empty folder for one test, laid out the way exports are.
+    fn folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("pupik_editor_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(folder.join("structures")).unwrap();
+        std::fs::create_dir_all(folder.join("structures_meta")).unwrap();
+        folder
+    }
+
+    #[test]
+    fn exported_structures_get_their_anchor_back() {
+        let folder = folder("anchor");
+        let path = folder.join("structures").join("rings");
+        std::fs::write(&path, "12\n0 0 regular\n-50 -200 planet\n10 10 unknown\n").unwrap();
+        let meta = StructureMeta {
+            origin: Some((30., 500.)),
+            ..Default::default()
+        };
+        std::fs::write(
+            folder.join("structures_meta").join("rings.ron"),
+            ron::to_string(&meta).unwrap(),
+        )
+        .unwrap();
+
+        let items = read_items(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&folder);
+        assert_eq!(
+            items,
+            vec![
+                (catalog::index_of("regular").unwrap(), Vec2::new(30., 500.)),
+                (catalog::index_of("planet").unwrap(), Vec2::new(-20., 300.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn exported_structures_without_anchor_are_read_as_they_are() {
+        let folder = folder("no_anchor");
+        let path = folder.join("structures").join("rings");
+        std::fs::write(&path, "12\n-50 -200 planet\n").unwrap();
+        let missing = read_items(&path);
+        std::fs::write(
+            folder.join("structures_meta").join("rings.ron"),
+            ron::to_string(&StructureMeta::default()).unwrap(),
+        )
+        .unwrap();
+        let old = read_items(&path);
+        let _ = std::fs::remove_dir_all(&folder);
+        let expected = vec![(catalog::index_of("planet").unwrap(), Vec2::new(-50., -200.))];
+        assert_eq!(missing.unwrap(), expected);
+        assert_eq!(old.unwrap(), expected);
+    }
+
+    #[test]
+    fn projects_are_read_as_they_are() {
+        let folder = folder("project");
+        let path = folder.join("rings.ron");
+        Project {
+            items: vec![ProjectItem {
+                name: "planet".to_string(),
+                x: -400.,
+                y: 12.5,
+                z: 0.,
+                layer: 0,
+            }],
+            ..Default::default()
+        }
+        .write(&path)
+        .unwrap();
+
+        let items = read_items(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&folder);
+        assert_eq!(
+            items,
+            vec![(catalog::index_of("planet").unwrap(), Vec2::new(-400., 12.5))]
+        );
+    }
+}
@@ -1,54 +1,96 @@
 use crate::{
-    mouse::{BuiltItem, ItemName},
+    anchor::{Anchor, AnchorMode},
+    catalog,
+    layers::Layers,
+    lint::{lint, HeldExport, Linting},
+    mouse::{BuiltItem, DrawOrder, ItemName},
+    passability::Player,
+    play::PlaySettings,
+    project::Dirty,
+    recent::RecentFiles,
+    scoring::{score, StructureMeta},
     structure_ui::{ApplyDefaultColoring, UISprite},
     {ExportSheet, UiState}, WhiteSheet,
 };
+use bevy::app::AppExit;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_egui::{egui::Rgba, *};
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
+const INPUT_PANEL_HEIGHT: f32 = 230.;
+
 #[derive(Component)]
 pub struct ExportButton;
 
 #[derive(Component)]
 pub struct SingleUse;
 
+#[derive(Event)]
+pub struct ExportStructure {
+    pub exit_after: bool,
+}
+
 pub struct ExportPlugin;
 
 impl Plugin for ExportPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(PostStartup, spawn_export_button)
-            .add_systems(Update, export_button_interaction)
+            .add_event::<ExportStructure>()
+            .add_systems(Update, (export_button_interaction, export_structure))
             .add_systems(Startup, user_input_background)
             .add_systems(Update, user_input);
     }
 }
 
-fn export(item_query: &Query<(&Transform, &ItemName), With<BuiltItem>>, ui_state: &Res<UiState>) {
+/// Objects that go into the exported file, from back to front, so spawning them in file order keeps the layering.
+fn export_list(
+    item_query: &Query<(&Transform, &ItemName, &DrawOrder, &Visibility), With<BuiltItem>>,
+    layers: &Layers,
+) -> Vec<(Vec2, &'static str)> {
+    let mut items: Vec<_> = item_query
+        .iter()
+        .filter(|(_, _, _, visibility)| layers.export_hidden || **visibility != Visibility::Hidden)
+        .collect();
+    items.sort_by_key(|(_, _, order, _)| **order);
+    items
+        .into_iter()
+        .map(|(transform, name, _, _)| (transform.translation.truncate(), name.name))
+        .collect()
+}
+
+fn export(
+    items: &[(Vec2, &'static str)],
+    ui_state: &Res<UiState>,
+    anchor: &Res<Anchor>,
+    recent: &mut ResMut<RecentFiles>,
+) -> Option<Vec2> {
     if let Err(_) = std::fs::create_dir_all("./structures") {
-        return;
+        return None;
     };
     if !ui_state.ready_to_export {
-        return;
+        return None;
     };
     let export_path = "./structures/".to_owned() + if ui_state.name == "" {"export"} else {&ui_state.name};
     let weight = &ui_state.weight_s;
-    let file = File::create(export_path).expect("Unable to create file");
+    let file = File::create(&export_path).expect("Unable to create file");
     let mut file = BufWriter::new(file);
     file.write_all((weight.to_owned() + "\n").as_bytes()).expect("Unable to write into created file");
-    for (transform, name) in item_query.iter() {
-        let trans = transform.translation;
+    let origin = anchor.point(items.iter().map(|(position, _)| *position));
+    for (position, name) in items {
+        let trans = *position - origin;
         let line: String = (trans.x as i32).to_string()
             + " "
             + &(trans.y as i32).to_string()
             + " "
-            + name.name
+            + name
             + "\n";
         file.write_all(line.as_bytes())
             .expect("Unable to write into created file");
     }
+    recent.add(std::path::Path::new(&export_path));
+    Some(origin)
 }
 
 pub fn spawn_export_button(
@@ -115,18 +157,24 @@ fn user_input_background(
             sprite: TextureAtlasSprite::new(0),
             texture_atlas: sheet.0.clone(),
             transform: Transform {
-                translation: Vec3::new((1920. / 3.) / 2. + scale / 2., w_height * 0.117, 901.),
-                scale: Vec3::new(scale,165.,1.),
+                translation: Vec3::new(
+                    (1920. / 3.) / 2. + scale / 2.,
+                    w_height * 0.117 + (165. - INPUT_PANEL_HEIGHT) / 2.,
+                    901.,
+                ),
+                scale: Vec3::new(scale, INPUT_PANEL_HEIGHT, 1.),
                 ..Default::default()
             },
             ..Default::default()
         })
         .insert(UISprite {
-            sprite_size: Vec2::new(scale, 165.),
+            sprite_size: Vec2::new(scale, INPUT_PANEL_HEIGHT),
         });
 }
 fn user_input(
     mut ui_state: ResMut<UiState>,
+    mut anchor: ResMut<Anchor>,
+    mut dirty: ResMut<Dirty>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
     mut contexts: EguiContexts,
 ) {
@@ -148,7 +196,9 @@ fn user_input(
             ui.horizontal(|ui| {
                 ui.colored_label(
                     Rgba::BLACK,"File name:");
-                ui.text_edit_singleline(&mut ui_state.name);
+                if ui.text_edit_singleline(&mut ui_state.name).changed() {
+                    dirty.mark();
+                }
             });
             
             ui_state.name = ui_state.name.replace('.', "");
@@ -156,7 +206,9 @@ fn user_input(
             ui.horizontal(|ui| {
                 ui.colored_label(
                     Rgba::BLACK,"Relative weight:");
-                ui.text_edit_singleline(&mut ui_state.weight_s);
+                if ui.text_edit_singleline(&mut ui_state.weight_s).changed() {
+                    dirty.mark();
+                }
             });
             ui_state.weight_s = ui_state.weight_s.trim().replace(',', ".");
             if let Err(_e) = ui_state.weight_s.parse::<f64>(){
@@ -170,6 +222,15 @@ fn user_input(
 
             }
 
+            ui.colored_label(Rgba::BLACK, "Anchor:");
+            ui.horizontal(|ui| {
+                for mode in AnchorMode::ALL {
+                    if ui.selectable_value(&mut anchor.mode, mode, mode.label()).changed() {
+                        dirty.mark();
+                    }
+                }
+            });
+
             ui.colored_label(
                 Rgba::BLACK,
                 "Some example weights:\n   Rainbow is 0.2\n   Basic enemy is 119\n   Energy bar is 12",
@@ -180,15 +241,61 @@ fn user_input(
 
 fn export_button_interaction(
     eraser_button_q: Query<&Interaction, (Changed<Interaction>, With<ExportButton>)>,
-    item_query: Query<(&Transform, &ItemName), With<BuiltItem>>,
-    ui_state: Res<UiState>,
+    mut export_event: EventWriter<ExportStructure>,
 ) {
     for interaction in eraser_button_q.iter() {
         match *interaction {
             Interaction::Pressed => {
-                export(&item_query, &ui_state);
+                export_event.send(ExportStructure { exit_after: false });
             }
             _ => {}
         }
     }
 }
+
+fn export_structure(
+    mut events: EventReader<ExportStructure>,
+    item_query: Query<(&Transform, &ItemName, &DrawOrder, &Visibility), With<BuiltItem>>,
+    ui_state: Res<UiState>,
+    anchor: Res<Anchor>,
+    layers: Res<Layers>,
+    mut dirty: ResMut<Dirty>,
+    mut recent: ResMut<RecentFiles>,
+    mut exit: EventWriter<AppExit>,
+    settings: Res<PlaySettings>,
+    mut linting: ResMut<Linting>,
+) {
+    for event in events.iter() {
+        let items = export_list(&item_query, &layers);
+        // Checked and scored exactly as they are written.
+        let checked: Vec<(usize, Vec2)> = items
+            .iter()
+            .filter_map(|(position, name)| Some((catalog::index_of(name)?, *position)))
+            .collect();
+        if ui_state.ready_to_export && !std::mem::take(&mut linting.allow_next) {
+            let problems = lint(&checked, &linting.rules, Player::from(&*settings));
+            if !problems.is_empty() {
+                linting.held = Some(HeldExport {
+                    exit_after: event.exit_after,
+                    problems,
+                });
+                continue;
+            }
+        }
+        if let Some(origin) = export(&items, &ui_state, &anchor, &mut recent) {
+            let meta = StructureMeta {
+                weight: ui_state.weight_s.clone(),
+                origin: Some((origin.x, origin.y)),
+                scores: score(&checked, Player::from(&*settings)),
+            };
+            let name = if ui_state.name.is_empty() { "export" } else { &ui_state.name };
+            if let Err(e) = meta.write(name) {
+                warn!("Writing the scores of {} failed: {}", name, e);
+            }
+            dirty.clear();
+            if event.exit_after {
+                exit.send(AppExit);
+            }
+        }
+    }
+}
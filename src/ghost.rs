@@ -0,0 +1,53 @@
+use crate::CombinedSheet;
+use bevy::prelude::*;
+
+const GHOST_ALPHA: f32 = 0.4;
+/// Above every placed object, below the UI sprites.
+const GHOST_Z: f32 = 850.;
+
+/// Objects a tool is about to place. Tools fill it every frame and it is emptied again before the next one.
+#[derive(Resource, Default)]
+pub struct GhostPreview(pub Vec<(usize, Vec2)>);
+
+#[derive(Component)]
+struct Ghost;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostPreview>()
+            .add_systems(First, |mut preview: ResMut<GhostPreview>| preview.0.clear())
+            .add_systems(PostUpdate, sync_ghosts);
+    }
+}
+
+fn sync_ghosts(
+    mut commands: Commands,
+    preview: Res<GhostPreview>,
+    texture_atlas: Res<CombinedSheet>,
+    mut ghost_q: Query<(Entity, &mut Transform, &mut TextureAtlasSprite), With<Ghost>>,
+) {
+    let mut ghosts = ghost_q.iter_mut();
+    for (index, position) in preview.0.iter() {
+        let translation = position.extend(GHOST_Z);
+        if let Some((_, mut trans, mut sprite)) = ghosts.next() {
+            trans.translation = translation;
+            sprite.index = *index;
+        } else {
+            let mut sprite = TextureAtlasSprite::new(*index);
+            sprite.color.set_a(GHOST_ALPHA);
+            commands
+                .spawn(SpriteSheetBundle {
+                    sprite,
+                    texture_atlas: texture_atlas.0.clone(),
+                    transform: Transform::from_translation(translation),
+                    ..Default::default()
+                })
+                .insert(Ghost);
+        }
+    }
+    for (entity, _, _) in ghosts {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    cache::{Cached, StructureVersion},
+    catalog::{self, Gravity},
+    mouse::BuiltItem,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// Most cells the combined field is drawn with, the cell size grows to stay below it.
+const MAX_CELLS: f32 = 6000.;
+
+#[derive(Resource)]
+struct GravityOverlay {
+    radius: bool,
+    field: bool,
+    /// Size of one cell of the combined field.
+    cell: f32,
+}
+
+impl Default for GravityOverlay {
+    fn default() -> Self {
+        GravityOverlay {
+            radius: true,
+            field: false,
+            cell: 40.,
+        }
+    }
+}
+
+/// Pull of all `sources` together at `point`. Pulls from opposite sides cancel out.
+fn field_at(sources: &[(Vec2, Gravity)], point: Vec2) -> Vec2 {
+    sources
+        .iter()
+        .map(|(centre, gravity)| {
+            let offset = *centre - point;
+            offset.normalize_or_zero() * gravity.pull_at(offset.length())
+        })
+        .sum()
+}
+
+/// Samples the combined field over everything the pulls reach, as centre, pull and size of each cell.
+fn field_cells(sources: &[(Vec2, Gravity)], cell: f32) -> Vec<(Vec2, Vec2, f32)> {
+    let mut cells = Vec::new();
+    if sources.is_empty() {
+        return cells;
+    }
+    let min = sources
+        .iter()
+        .map(|(centre, gravity)| *centre - gravity.radius)
+        .fold(Vec2::MAX, Vec2::min);
+    let max = sources
+        .iter()
+        .map(|(centre, gravity)| *centre + gravity.radius)
+        .fold(Vec2::MIN, Vec2::max);
+    let area = (max - min).x * (max - min).y;
+    let cell = cell.max((area / MAX_CELLS).sqrt());
+    let mut y = min.y + cell / 2.;
+    while y < max.y {
+        let mut x = min.x + cell / 2.;
+        while x < max.x {
+            let point = Vec2::new(x, y);
+            let pull = field_at(sources, point);
+            if pull != Vec2::ZERO {
+                cells.push((point, pull, cell));
+            }
+            x += cell;
+        }
+        y += cell;
+    }
+    cells
+}
+
+/// Goes from yellow for a weak pull to red for the strongest one.
+fn heat(share: f32) -> Color {
+    let share = share.clamp(0., 1.);
+    Color::rgba(1., 1. - share, 0., 0.15 + share * 0.6)
+}
+
+#[derive(Resource, Default)]
+struct FieldCache(Cached<(u64, f32), Vec<(Vec2, Vec2, f32)>>);
+
+pub struct GravityPlugin;
+
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GravityOverlay>()
+            .init_resource::<FieldCache>()
+            .add_systems(Update, gravity_window);
+    }
+}
+
+fn gravity_window(
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    version: Res<StructureVersion>,
+    mut cache: ResMut<FieldCache>,
+    mut overlay: ResMut<GravityOverlay>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    let sources: Vec<(Vec2, Gravity)> = item_q
+        .iter()
+        .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+        .filter_map(|(trans, sprite, _)| {
+            Some((
+                trans.translation.truncate(),
+                catalog::gravity_of(sprite.index)?,
+            ))
+        })
+        .collect();
+
+    // Only sampled again once the structure or the cell size changed.
+    let cells: &[(Vec2, Vec2, f32)] = if overlay.field {
+        cache.0.get((version.0, overlay.cell), || {
+            field_cells(&sources, overlay.cell)
+        })
+    } else {
+        &[]
+    };
+    let strongest = cells
+        .iter()
+        .max_by(|a, b| a.1.length().total_cmp(&b.1.length()))
+        .map(|(point, pull, _)| (*point, pull.length()));
+
+    egui::Window::new("Gravity")
+        .default_pos(egui::pos2(660., 105.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut overlay.radius, "Show how far each object pulls");
+            ui.checkbox(&mut overlay.field, "Show the combined field");
+            ui.horizontal(|ui| {
+                ui.label("Field cell size:");
+                ui.add(egui::DragValue::new(&mut overlay.cell).clamp_range(10..=200));
+            });
+            if sources.is_empty() {
+                ui.label("No black holes or planets");
+            } else if let Some((point, pull)) = strongest {
+                ui.label(format!(
+                    "Strongest pull {:.2} at x {:.0}, y {:.0}",
+                    pull, point.x, point.y
+                ));
+            }
+        });
+
+    if overlay.radius {
+        for (centre, gravity) in sources.iter() {
+            gizmos
+                .circle_2d(*centre, gravity.radius, heat(0.))
+                .segments(64);
+            // Rings where the pull is at three quarters, a half and a quarter of its strength.
+            for share in [0.25, 0.5, 0.75] {
+                gizmos
+                    .circle_2d(*centre, gravity.radius * (1. - share), heat(share))
+                    .segments(48);
+            }
+        }
+    }
+    let Some((point, strongest)) = strongest else {
+        return;
+    };
+    for (centre, pull, cell) in cells.iter() {
+        let share = pull.length() / strongest;
+        gizmos.rect_2d(*centre, 0., Vec2::splat(*cell * 0.8), heat(share));
+        // Points where the player is pulled to, longer for a stronger pull.
+        let direction = pull.normalize_or_zero() * *cell * 0.4 * share;
+        gizmos.line_2d(*centre, *centre + direction, heat(share));
+    }
+    gizmos.circle_2d(point, 10., Color::RED);
+}
@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+const GUIDE_SNAP_DISTANCE: f32 = 8.;
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct GridSettings {
+    pub visible: bool,
+    pub snap: bool,
+    pub spacing: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        GridSettings {
+            visible: false,
+            snap: false,
+            spacing: 40.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GuideAxis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Guide {
+    pub axis: GuideAxis,
+    pub position: f32,
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct Guides(pub Vec<Guide>);
+
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridSettings>()
+            .init_resource::<Guides>()
+            .add_systems(Update, (draw_grid, grid_window));
+    }
+}
+
+/// Guides within reach win over the grid.
+pub fn snap_position(grid: &GridSettings, guides: &Guides, position: Vec2) -> Vec2 {
+    if !grid.snap {
+        return position;
+    }
+    let mut snapped = if grid.spacing > 0. {
+        (position / grid.spacing).round() * grid.spacing
+    } else {
+        position
+    };
+    for guide in guides.0.iter() {
+        match guide.axis {
+            GuideAxis::Horizontal if (position.y - guide.position).abs() < GUIDE_SNAP_DISTANCE => {
+                snapped.y = guide.position;
+            }
+            GuideAxis::Vertical if (position.x - guide.position).abs() < GUIDE_SNAP_DISTANCE => {
+                snapped.x = guide.position;
+            }
+            _ => {}
+        }
+    }
+    snapped
+}
+
+fn draw_grid(
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    camera_q: Query<&Transform, With<Camera>>,
+    windows_q: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos,
+) {
+    let window = windows_q.single();
+    let centre = camera_q.single().translation.truncate();
+    let half = Vec2::new(window.width(), window.height()) / 2.;
+    let (min, max) = (centre - half, centre + half);
+
+    if grid.visible && grid.spacing >= 5. {
+        let color = Color::rgba(0., 0., 0., 0.15);
+        let mut x = (min.x / grid.spacing).ceil() * grid.spacing;
+        while x < max.x {
+            gizmos.line_2d(Vec2::new(x, min.y), Vec2::new(x, max.y), color);
+            x += grid.spacing;
+        }
+        let mut y = (min.y / grid.spacing).ceil() * grid.spacing;
+        while y < max.y {
+            gizmos.line_2d(Vec2::new(min.x, y), Vec2::new(max.x, y), color);
+            y += grid.spacing;
+        }
+    }
+
+    let color = Color::rgb(0., 0.6, 0.9);
+    for guide in guides.0.iter() {
+        match guide.axis {
+            GuideAxis::Horizontal => gizmos.line_2d(
+                Vec2::new(min.x, guide.position),
+                Vec2::new(max.x, guide.position),
+                color,
+            ),
+            GuideAxis::Vertical => gizmos.line_2d(
+                Vec2::new(guide.position, min.y),
+                Vec2::new(guide.position, max.y),
+                color,
+            ),
+        }
+    }
+}
+
+fn grid_window(
+    mut grid: ResMut<GridSettings>,
+    mut guides: ResMut<Guides>,
+    camera_q: Query<&Transform, With<Camera>>,
+    mut contexts: EguiContexts,
+) {
+    let camera_y = camera_q.single().translation.y;
+    egui::Window::new("Grid and guides")
+        .default_pos(egui::pos2(15., 210.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut grid.visible, "Show grid");
+            ui.checkbox(&mut grid.snap, "Snap to grid and guides");
+            ui.horizontal(|ui| {
+                ui.label("Spacing:");
+                ui.add(egui::DragValue::new(&mut grid.spacing).clamp_range(5.0..=400.0));
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("+ Horizontal").clicked() {
+                    guides.0.push(Guide {
+                        axis: GuideAxis::Horizontal,
+                        position: camera_y.round(),
+                    });
+                }
+                if ui.button("+ Vertical").clicked() {
+                    guides.0.push(Guide {
+                        axis: GuideAxis::Vertical,
+                        position: 0.,
+                    });
+                }
+            });
+            let mut removed = None;
+            for (i, guide) in guides.0.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(match guide.axis {
+                        GuideAxis::Horizontal => "y =",
+                        GuideAxis::Vertical => "x =",
+                    });
+                    ui.add(egui::DragValue::new(&mut guide.position));
+                    if ui.button("x").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                guides.0.remove(i);
+            }
+        });
+}
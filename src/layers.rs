@@ -0,0 +1,230 @@
+use crate::{
+    catalog,
+    mouse::{BuiltItem, Selected},
+    project::Dirty,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LayerState {
+    pub visible: bool,
+    pub locked: bool,
+}
+
+impl Default for LayerState {
+    fn default() -> Self {
+        LayerState {
+            visible: true,
+            locked: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CustomLayer {
+    pub id: u32,
+    pub name: String,
+    pub state: LayerState,
+}
+
+/// Objects are grouped both by their type and by a user defined layer, either of them can hide or lock an object.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layers {
+    pub types: Vec<LayerState>,
+    pub custom: Vec<CustomLayer>,
+    /// Layer new objects are placed into.
+    pub active: u32,
+    pub export_hidden: bool,
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Layers {
+            types: vec![LayerState::default(); catalog::OBJECTS.len()],
+            custom: vec![CustomLayer {
+                id: 0,
+                name: "Default".to_string(),
+                state: LayerState::default(),
+            }],
+            active: 0,
+            export_hidden: false,
+        }
+    }
+}
+
+impl Layers {
+    fn layer_state(&self, id: u32) -> Option<&LayerState> {
+        self.custom
+            .iter()
+            .find(|layer| layer.id == id)
+            .map(|layer| &layer.state)
+    }
+
+    pub fn is_visible(&self, index: usize, layer: u32) -> bool {
+        self.types.get(index).is_none_or(|state| state.visible)
+            && self.layer_state(layer).is_none_or(|state| state.visible)
+    }
+
+    /// Hidden objects count as locked too, nobody should edit what they can't see.
+    pub fn is_locked(&self, index: usize, layer: u32) -> bool {
+        !self.is_visible(index, layer)
+            || self.types.get(index).is_some_and(|state| state.locked)
+            || self.layer_state(layer).is_some_and(|state| state.locked)
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct ItemLayer(pub u32);
+
+/// Marks objects that can't be selected, erased or moved right now.
+#[derive(Component)]
+pub struct Locked;
+
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Layers>()
+            .add_systems(Update, (layers_window, assign_new_items, apply_layers));
+    }
+}
+
+fn assign_new_items(
+    mut commands: Commands,
+    new_q: Query<Entity, (Added<BuiltItem>, Without<ItemLayer>)>,
+    layers: Res<Layers>,
+) {
+    for entity in new_q.iter() {
+        commands.entity(entity).insert(ItemLayer(layers.active));
+    }
+}
+
+fn apply_layers(
+    mut commands: Commands,
+    layers: Res<Layers>,
+    mut item_q: Query<
+        (
+            Entity,
+            &TextureAtlasSprite,
+            &ItemLayer,
+            &mut Visibility,
+            Option<&Locked>,
+        ),
+        With<BuiltItem>,
+    >,
+) {
+    for (entity, sprite, layer, mut visibility, locked) in item_q.iter_mut() {
+        let visible = if layers.is_visible(sprite.index, layer.0) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != visible {
+            *visibility = visible;
+        }
+        match (layers.is_locked(sprite.index, layer.0), locked.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(Locked).remove::<Selected>();
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Locked>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn layer_row(ui: &mut egui::Ui, label: &str, state: &mut LayerState) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut state.visible, "Visible").changed();
+        changed |= ui.checkbox(&mut state.locked, "Locked").changed();
+        ui.label(label);
+    });
+    changed
+}
+
+fn layers_window(
+    mut commands: Commands,
+    mut layers: ResMut<Layers>,
+    selected_q: Query<Entity, (With<Selected>, With<BuiltItem>)>,
+    item_q: Query<(Entity, &ItemLayer), With<BuiltItem>>,
+    mut dirty: ResMut<Dirty>,
+    mut contexts: EguiContexts,
+) {
+    let mut changed = false;
+    egui::Window::new("Layers")
+        .default_pos(egui::pos2(15., 255.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Object types:");
+            for (object, state) in catalog::OBJECTS.iter().zip(layers.types.iter_mut()) {
+                changed |= layer_row(ui, object.name, state);
+            }
+            ui.separator();
+            ui.label("Layers, new objects go into the active one:");
+            let active = layers.active;
+            let mut new_active = None;
+            let mut removed = None;
+            let can_remove = layers.custom.len() > 1;
+            for layer in layers.custom.iter_mut() {
+                ui.horizontal(|ui| {
+                    if ui.radio(layer.id == active, "").clicked() {
+                        new_active = Some(layer.id);
+                    }
+                    changed |= ui.checkbox(&mut layer.state.visible, "").changed();
+                    changed |= ui.checkbox(&mut layer.state.locked, "").changed();
+                    changed |= ui.text_edit_singleline(&mut layer.name).changed();
+                    if ui
+                        .button("Move selection here")
+                        .on_hover_text("Moves the selected objects into this layer")
+                        .clicked()
+                    {
+                        for entity in selected_q.iter() {
+                            commands.entity(entity).insert(ItemLayer(layer.id));
+                        }
+                        changed = true;
+                    }
+                    if can_remove && ui.button("x").clicked() {
+                        removed = Some(layer.id);
+                    }
+                });
+            }
+            if let Some(id) = new_active {
+                layers.active = id;
+            }
+            if ui.button("+ Layer").clicked() {
+                let id = layers.custom.iter().map(|l| l.id + 1).max().unwrap_or(0);
+                layers.custom.push(CustomLayer {
+                    id,
+                    name: format!("Layer {}", id),
+                    state: LayerState::default(),
+                });
+                layers.active = id;
+                changed = true;
+            }
+            if let Some(id) = removed {
+                layers.custom.retain(|layer| layer.id != id);
+                if layers.active == id {
+                    layers.active = layers.custom[0].id;
+                }
+                for (entity, layer) in item_q.iter() {
+                    if layer.0 == id {
+                        commands.entity(entity).insert(ItemLayer(layers.active));
+                    }
+                }
+                changed = true;
+            }
+            ui.separator();
+            changed |= ui
+                .checkbox(&mut layers.export_hidden, "Export hidden objects")
+                .changed();
+        });
+    if changed {
+        dirty.mark();
+    }
+}
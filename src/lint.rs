@@ -0,0 +1,387 @@
+use crate::{
+    cache::{Cached, StructureVersion},
+    catalog::{self, ObjectRole},
+    export::ExportStructure,
+    layers::Layers,
+    mouse::BuiltItem,
+    overlap::exact_duplicates,
+    passability::{self, Player},
+    play::PlaySettings,
+    scoring::exported_items,
+    PLAY_COLUMN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Shared by the editor and `--lint`, so both check the same things.
+pub const LINT_FILE: &str = "./lint.ron";
+/// How long a problem jumped to stays circled.
+const FOCUS_SECONDS: f32 = 2.;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rule {
+    SideBars,
+    PlaneSpacing,
+    Gap,
+    Height,
+    Duplicate,
+    Empty,
+}
+
+impl Rule {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Rule::SideBars => "side bars",
+            Rule::PlaneSpacing => "plane spacing",
+            Rule::Gap => "gap",
+            Rule::Height => "height",
+            Rule::Duplicate => "duplicate",
+            Rule::Empty => "empty",
+        }
+    }
+}
+
+/// Which rules are checked and their limits.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LintRules {
+    /// Damaging objects completely behind the side bars, the player never meets them.
+    pub side_bars: bool,
+    pub plane_spacing: bool,
+    pub min_plane_spacing: f32,
+    /// The player has to fit through somewhere, see passability.rs.
+    pub gap: bool,
+    pub height: bool,
+    pub max_height: f32,
+    /// Objects of the same type less than a pixel apart.
+    pub duplicates: bool,
+    pub empty: bool,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            side_bars: true,
+            plane_spacing: true,
+            min_plane_spacing: 300.,
+            gap: true,
+            height: false,
+            max_height: 5000.,
+            duplicates: true,
+            empty: true,
+        }
+    }
+}
+
+impl LintRules {
+    /// The saved rules, or the defaults if there are none.
+    pub fn load() -> LintRules {
+        std::fs::read_to_string(LINT_FILE)
+            .ok()
+            .and_then(|text| ron::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Ok(text) = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            let _ = std::fs::write(Path::new(LINT_FILE), text);
+        }
+    }
+}
+
+pub struct Problem {
+    pub rule: Rule,
+    pub message: String,
+    /// Where to look, None for problems of the whole structure.
+    pub at: Option<Vec2>,
+}
+
+pub fn lint(items: &[(usize, Vec2)], rules: &LintRules, player: Player) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if items.is_empty() {
+        if rules.empty {
+            problems.push(Problem {
+                rule: Rule::Empty,
+                message: "There are no objects to export".to_string(),
+                at: None,
+            });
+        }
+        return problems;
+    }
+
+    if rules.side_bars {
+        for (index, position) in items.iter() {
+            let size = catalog::size_of(*index);
+            let hidden = position.x.abs() - size.x / 2. >= PLAY_COLUMN_WIDTH / 2.;
+            if hidden && catalog::role_of(*index) == ObjectRole::Hazard {
+                problems.push(Problem {
+                    rule: Rule::SideBars,
+                    message: format!("{} is behind the side bar", catalog::name_of(*index)),
+                    at: Some(*position),
+                });
+            }
+        }
+    }
+
+    if rules.plane_spacing {
+        let mut planes: Vec<Vec2> = items
+            .iter()
+            .filter(|(index, _)| catalog::role_of(*index) == ObjectRole::Sensor)
+            .map(|(_, position)| *position)
+            .collect();
+        planes.sort_by(|a, b| b.y.total_cmp(&a.y));
+        for pair in planes.windows(2) {
+            let spacing = pair[0].y - pair[1].y;
+            if spacing < rules.min_plane_spacing {
+                problems.push(Problem {
+                    rule: Rule::PlaneSpacing,
+                    message: format!(
+                        "Planes only {:.0} px apart, at least {:.0} wanted",
+                        spacing, rules.min_plane_spacing
+                    ),
+                    at: Some(pair[1]),
+                });
+            }
+        }
+    }
+
+    if rules.gap {
+        let analysis = passability::analyse(items, player);
+        if !analysis.passable {
+            let choke = analysis.chokes.last();
+            problems.push(Problem {
+                rule: Rule::Gap,
+                message: format!(
+                    "The player can't get through: {}",
+                    choke.map_or("", |c| c.reason.label())
+                ),
+                at: choke.map(|c| Vec2::new((c.from + c.to) / 2., c.y)),
+            });
+        }
+    }
+
+    if rules.height {
+        let top = items
+            .iter()
+            .map(|(i, p)| p.y + catalog::size_of(*i).y / 2.)
+            .fold(f32::MIN, f32::max);
+        let bottom = items
+            .iter()
+            .map(|(i, p)| p.y - catalog::size_of(*i).y / 2.)
+            .fold(f32::MAX, f32::min);
+        if top - bottom > rules.max_height {
+            problems.push(Problem {
+                rule: Rule::Height,
+                message: format!(
+                    "The structure is {:.0} px tall, at most {:.0} allowed",
+                    top - bottom,
+                    rules.max_height
+                ),
+                at: Some(Vec2::new(0., bottom + rules.max_height)),
+            });
+        }
+    }
+
+    if rules.duplicates {
+        for i in exact_duplicates(items) {
+            let (index, position) = items[i];
+            problems.push(Problem {
+                rule: Rule::Duplicate,
+                message: format!("{} placed twice at the same spot", catalog::name_of(index)),
+                at: Some(position),
+            });
+        }
+    }
+    problems
+}
+
+/// An export stopped by problems, waiting for the user to look at them.
+pub struct HeldExport {
+    pub exit_after: bool,
+    /// Found in what the export would have written.
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Resource)]
+pub struct Linting {
+    pub rules: LintRules,
+    pub held: Option<HeldExport>,
+    /// Lets the next export through without checking again.
+    pub allow_next: bool,
+    focus: Option<(Vec2, f32)>,
+}
+
+#[derive(Resource, Default)]
+struct LintCache(Cached<(u64, Player, LintRules), Vec<Problem>>);
+
+pub struct LintPlugin;
+
+impl Plugin for LintPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Linting {
+            rules: LintRules::load(),
+            held: None,
+            allow_next: false,
+            focus: None,
+        })
+        .init_resource::<LintCache>()
+        .add_systems(Update, (problems_window, held_export_dialog));
+    }
+}
+
+fn problems_window(
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    layers: Res<Layers>,
+    settings: Res<PlaySettings>,
+    time: Res<Time>,
+    version: Res<StructureVersion>,
+    mut cache: ResMut<LintCache>,
+    mut linting: ResMut<Linting>,
+    mut camera_q: Query<&mut Transform, (With<Camera>, Without<BuiltItem>)>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    let key = (version.0, Player::from(&*settings), linting.rules.clone());
+    // Collapsed, the count is only shown while it is still up to date.
+    let title = match cache.0.fresh(&key) {
+        Some(problems) => format!("Problems ({})", problems.len()),
+        None => "Problems".to_string(),
+    };
+    let linting = &mut *linting;
+    egui::Window::new(title)
+        .id(egui::Id::new("Problems"))
+        .default_pos(egui::pos2(660., 195.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let rules = &mut linting.rules;
+            let before = ron::to_string(rules).ok();
+            ui.collapsing("Rules", |ui| {
+                ui.checkbox(
+                    &mut rules.side_bars,
+                    "No damaging objects behind the side bars",
+                );
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut rules.plane_spacing, "Planes at least");
+                    ui.add(
+                        egui::DragValue::new(&mut rules.min_plane_spacing).clamp_range(0..=5000),
+                    );
+                    ui.label("px apart");
+                });
+                ui.checkbox(&mut rules.gap, "The player fits through");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut rules.height, "At most");
+                    ui.add(egui::DragValue::new(&mut rules.max_height).clamp_range(100..=100000));
+                    ui.label("px tall");
+                });
+                ui.checkbox(&mut rules.duplicates, "No duplicated objects");
+                ui.checkbox(&mut rules.empty, "Not empty");
+            });
+            if ron::to_string(rules).ok() != before {
+                rules.save();
+            }
+            let problems = cache.0.get(key.clone(), || {
+                lint(&exported_items(&item_q, &layers), &key.2, key.1)
+            });
+            if problems.is_empty() {
+                ui.label("No problems found.");
+            }
+            egui::ScrollArea::vertical()
+                .max_height(250.)
+                .show(ui, |ui| {
+                    for problem in problems.iter() {
+                        let text = format!("[{}] {}", problem.rule.label(), problem.message);
+                        let Some(at) = problem.at else {
+                            ui.label(text);
+                            continue;
+                        };
+                        if ui.link(text).clicked() {
+                            for mut camera in camera_q.iter_mut() {
+                                camera.translation.y = at.y;
+                            }
+                            linting.focus = Some((at, time.elapsed_seconds()));
+                        }
+                    }
+                });
+        });
+
+    if let Some((at, since)) = linting.focus {
+        if time.elapsed_seconds() - since > FOCUS_SECONDS {
+            linting.focus = None;
+        } else {
+            gizmos.circle_2d(at, 70., Color::RED).segments(48);
+        }
+    }
+}
+
+/// Shown when an export found problems, so they can be fixed or exported anyway.
+fn held_export_dialog(
+    mut linting: ResMut<Linting>,
+    mut export: EventWriter<ExportStructure>,
+    mut contexts: EguiContexts,
+) {
+    let Some(held) = linting.held.take() else {
+        return;
+    };
+    let mut export_anyway = false;
+    let mut cancel = false;
+    egui::Window::new("Export anyway?")
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., 0.))
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!(
+                "The structure has {} problems, they are listed in the \"Problems\" window.",
+                held.problems.len()
+            ));
+            for problem in held.problems.iter().take(5) {
+                ui.label(format!("[{}] {}", problem.rule.label(), problem.message));
+            }
+            ui.horizontal(|ui| {
+                export_anyway = ui.button("Export anyway").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+    if export_anyway {
+        linting.allow_next = true;
+        export.send(ExportStructure {
+            exit_after: held.exit_after,
+        });
+    } else if !cancel {
+        linting.held = Some(held);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Player {
+        Player::from(&PlaySettings::default())
+    }
+
+    #[test]
+    fn empty_structure_is_one_problem() {
+        let problems = lint(&[], &LintRules::default(), player());
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].rule == Rule::Empty);
+    }
+
+    #[test]
+    fn hazards_behind_the_side_bars_are_found() {
+        let regular = catalog::index_of("regular").unwrap();
+        let items = [
+            (regular, Vec2::new(400., 0.)),
+            (regular, Vec2::new(-250., 0.)),
+        ];
+        let problems = lint(&items, &LintRules::default(), player());
+        let hidden: Vec<&Problem> = problems
+            .iter()
+            .filter(|problem| problem.rule == Rule::SideBars)
+            .collect();
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(hidden[0].at, Some(Vec2::new(400., 0.)));
+    }
+}
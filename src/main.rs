@@ -1,4 +1,5 @@
 #![windows_subsystem = "windows"]
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
 
 use bevy::prelude::*;
 use bevy::window::*;
@@ -8,12 +9,23 @@ use winit::window::Icon;
 
 pub const CLEAR: Color = Color::rgb(0.75, 0.70, 1.);
 pub const RESOLUTION: f32 = 1920. / 1080.;
+/// Width of the part of a structure the player actually sees, centred at x = 0.
+pub const PLAY_COLUMN_WIDTH: f32 = 1920. / 3.;
+/// Height of the game's screen, taller than the editor's canvas.
+pub const GAME_HEIGHT: f32 = 1080.;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Default, States)]
 pub enum GameState {
     #[default]
     Building,
     Erasing,
+    Selecting,
+    Patterning,
+    Pathing,
+    Stamping,
+    Playing,
+    Simulating,
+    Measuring,
 }
 
 #[derive(Resource)]
@@ -48,20 +60,81 @@ pub struct LoveSheet(pub Handle<TextureAtlas>);
 struct UiState {
     name: String,
     weight_s: String,
+    notes: String,
     ready_to_export: bool,
 }
 
+mod anchor;
+mod balance;
+mod brush;
+mod cache;
+mod catalog;
+mod cli;
 mod export;
+mod ghost;
+mod gravity;
+mod grid;
+mod layers;
+mod lint;
+mod measure;
 mod mouse;
+mod overlap;
+mod passability;
+mod path;
+mod play;
+mod pattern;
+mod project;
+mod recent;
+mod recovery;
+mod scoring;
+mod selection;
+mod simulate;
+mod stamp;
+mod structure;
 mod structure_ui;
+mod symmetry;
+mod transform;
+mod viewport;
 
+use anchor::AnchorPlugin;
+use balance::BalancePlugin;
+use brush::BrushPlugin;
+use cache::CachePlugin;
 use export::ExportPlugin;
+use ghost::GhostPlugin;
+use gravity::GravityPlugin;
+use grid::GridPlugin;
+use layers::LayersPlugin;
+use lint::LintPlugin;
+use measure::MeasurePlugin;
 use mouse::MousePlugin;
+use overlap::OverlapPlugin;
+use passability::PassabilityPlugin;
+use path::PathPlugin;
+use pattern::PatternPlugin;
+use play::PlayPlugin;
+use project::ProjectPlugin;
+use recent::{RecentPlugin, StartupFile};
+use recovery::RecoveryPlugin;
+use scoring::ScoringPlugin;
+use selection::SelectionPlugin;
+use simulate::SimulatePlugin;
+use stamp::StampPlugin;
 use structure_ui::StructureUIPlugin;
+use symmetry::SymmetryPlugin;
+use transform::TransformPlugin;
+use viewport::ViewportPlugin;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+    let startup_file = args.first().map(std::path::PathBuf::from);
+
     App::new()
         .insert_resource(ClearColor(CLEAR))
+        .insert_resource(StartupFile(startup_file))
         .add_state::<GameState>()
         .init_resource::<UiState>()
         .add_plugins(
@@ -76,13 +149,45 @@ fn main() {
                         resizable: false,
                         ..Default::default()
                     }),
+                    close_when_requested: false,
                     ..Default::default()
                 }),
         )
         .add_systems(Startup, (set_window_icon, spawn_camera))
         .add_systems(PreStartup, load_all)
         .add_plugins(EguiPlugin)
-        .add_plugins((StructureUIPlugin, MousePlugin, ExportPlugin))
+        .add_plugins((
+            StructureUIPlugin,
+            MousePlugin,
+            ExportPlugin,
+            AnchorPlugin,
+            GridPlugin,
+            ProjectPlugin,
+            RecoveryPlugin,
+            RecentPlugin,
+            SelectionPlugin,
+            LayersPlugin,
+            GhostPlugin,
+            PatternPlugin,
+            PathPlugin,
+            BrushPlugin,
+            SymmetryPlugin,
+        ))
+        .add_plugins((
+            TransformPlugin,
+            StampPlugin,
+            PlayPlugin,
+            PassabilityPlugin,
+            ScoringPlugin,
+            GravityPlugin,
+            SimulatePlugin,
+            BalancePlugin,
+            LintPlugin,
+            OverlapPlugin,
+            MeasurePlugin,
+            ViewportPlugin,
+            CachePlugin,
+        ))
         .run();
 }
 
@@ -128,7 +233,15 @@ fn load_all(
         SheetInfo::new("energy_sheet.png", 243., 117., 1, 1, None, None),
         SheetInfo::new("blackhole_sheet.png", 223., 223., 1, 1, None, None),
         SheetInfo::new("lovesheet.png", 100., 100., 1, 1, None, None),
-        SheetInfo::new("combined_sheet.png", 2254. / 7., 223., 7, 1, None, None),
+        SheetInfo::new(
+            "combined_sheet.png",
+            catalog::SHEET_CELL.x,
+            catalog::SHEET_CELL.y,
+            catalog::SHEET_COLUMNS,
+            1,
+            None,
+            None,
+        ),
         SheetInfo::new("eraser.png", 256., 256., 1, 1, None, None),
         SheetInfo::new("white_transparent.png", 1., 1., 1, 1, None, None),
         SheetInfo::new("export.png", 218., 218., 1, 1, None, None),
@@ -0,0 +1,232 @@
+use crate::{
+    grid::{snap_position, GridSettings, Guides},
+    mouse::{topmost_item, world_to_screen, BuiltItem, EditorTool, Selected},
+    play::PlaySettings,
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
+    GameState,
+};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+/// Spacing labels get unreadable with more selected objects than this.
+const MAX_SPACING_LABELS: usize = 40;
+
+#[derive(Resource)]
+struct Measurement {
+    from: Option<Vec2>,
+    to: Option<Vec2>,
+    dragging: bool,
+    /// Ends on the middle of the object under the cursor instead of the cursor itself.
+    snap_to_objects: bool,
+    selection_spacing: bool,
+}
+
+impl Default for Measurement {
+    fn default() -> Self {
+        Measurement {
+            from: None,
+            to: None,
+            dragging: false,
+            snap_to_objects: true,
+            selection_spacing: true,
+        }
+    }
+}
+
+/// Time the player has between meeting two heights, the structure scrolls at the fall speed.
+fn fall_time(dy: f32, fall_speed: f32) -> f32 {
+    dy.abs() / fall_speed.max(1.)
+}
+
+pub fn label_painter(contexts: &mut EguiContexts) -> egui::Painter {
+    contexts.ctx_mut().layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("measure labels"),
+    ))
+}
+
+pub fn paint_label(painter: &egui::Painter, at: Vec2, text: String) {
+    let galley =
+        painter.layout_no_wrap(text, egui::FontId::proportional(14.), egui::Color32::WHITE);
+    let rect = egui::Align2::LEFT_CENTER.anchor_rect(egui::Rect::from_min_size(
+        egui::pos2(at.x, at.y),
+        galley.size(),
+    ));
+    // The text alone is hard to read on top of the sprites.
+    painter.rect_filled(rect.expand(3.), 3., egui::Color32::from_black_alpha(180));
+    painter.galley(rect.min, galley);
+}
+
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Measurement>()
+            .add_systems(
+                Update,
+                (measure, measure_window)
+                    .chain()
+                    .run_if(in_state(GameState::Measuring)),
+            )
+            .add_systems(Update, selection_spacing);
+    }
+}
+
+fn measure(
+    buttons: Res<Input<MouseButton>>,
+    editor_tool_q: Query<&Transform, With<EditorTool>>,
+    item_q: Query<(Entity, &Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    mut measurement: ResMut<Measurement>,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    let cursor = tool.translation.truncate();
+    let object = measurement
+        .snap_to_objects
+        .then(|| {
+            topmost_item(
+                cursor,
+                item_q
+                    .iter()
+                    .filter(|(_, _, _, visibility)| **visibility != Visibility::Hidden)
+                    .map(|(entity, trans, sprite, _)| (entity, trans, sprite)),
+            )
+        })
+        .flatten()
+        .and_then(|entity| item_q.get(entity).ok())
+        .map(|(_, trans, _, _)| trans.translation.truncate());
+    let point = object.unwrap_or_else(|| snap_position(&grid, &guides, cursor));
+
+    if buttons.just_pressed(MouseButton::Left) && !overlaps_ui(tool, &ui_q) && !over_egui.0 {
+        measurement.from = Some(point);
+        measurement.to = Some(point);
+        measurement.dragging = true;
+    } else if measurement.dragging {
+        measurement.to = Some(point);
+        if !buttons.pressed(MouseButton::Left) {
+            measurement.dragging = false;
+        }
+    }
+}
+
+fn measure_window(
+    mut measurement: ResMut<Measurement>,
+    mut settings: ResMut<PlaySettings>,
+    camera_q: Query<&Transform, With<Camera>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    let line = measurement.from.zip(measurement.to);
+    egui::Window::new("Measure")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Drag between two points or objects.");
+            ui.horizontal(|ui| {
+                ui.label("Fall speed:");
+                ui.add(egui::DragValue::new(&mut settings.fall_speed).clamp_range(10..=3000));
+                ui.label("px/s");
+            });
+            ui.checkbox(
+                &mut measurement.snap_to_objects,
+                "Measure from object centres",
+            );
+            ui.checkbox(
+                &mut measurement.selection_spacing,
+                "Show the spacing of selected objects",
+            );
+            if let Some((from, to)) = line {
+                let delta = to - from;
+                ui.separator();
+                ui.label(format!("Distance: {:.0} px", delta.length()));
+                ui.label(format!(
+                    "Across: {:.0} px   Down: {:.0} px",
+                    delta.x.abs(),
+                    delta.y.abs()
+                ));
+                ui.label(format!(
+                    "Time between them: {:.2} s",
+                    fall_time(delta.y, settings.fall_speed)
+                ));
+            }
+        });
+
+    let Some((from, to)) = line else {
+        return;
+    };
+    let colour = Color::rgb(0.2, 0.9, 1.);
+    gizmos.line_2d(from, to, colour);
+    gizmos.line_2d(from, Vec2::new(from.x, to.y), colour.with_a(0.4));
+    gizmos.line_2d(Vec2::new(from.x, to.y), to, colour.with_a(0.4));
+    gizmos.circle_2d(from, 5., colour);
+    gizmos.circle_2d(to, 5., colour);
+    let (Ok(camera), Ok(window)) = (camera_q.get_single(), q_windows.get_single()) else {
+        return;
+    };
+    let delta = to - from;
+    let painter = label_painter(&mut contexts);
+    paint_label(
+        &painter,
+        world_to_screen(window, camera, (from + to) / 2.) + Vec2::new(10., 0.),
+        format!(
+            "{:.0} px, {:.2} s",
+            delta.length(),
+            fall_time(delta.y, settings.fall_speed)
+        ),
+    );
+}
+
+/// Marks the vertical gaps between the selected objects, top to bottom, in any tool.
+fn selection_spacing(
+    measurement: Res<Measurement>,
+    settings: Res<PlaySettings>,
+    selected_q: Query<&Transform, (With<Selected>, With<BuiltItem>)>,
+    camera_q: Query<&Transform, With<Camera>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    if !measurement.selection_spacing {
+        return;
+    }
+    let mut positions: Vec<Vec2> = selected_q
+        .iter()
+        .map(|trans| trans.translation.truncate())
+        .collect();
+    if positions.len() < 2 || positions.len() > MAX_SPACING_LABELS {
+        return;
+    }
+    let (Ok(camera), Ok(window)) = (camera_q.get_single(), q_windows.get_single()) else {
+        return;
+    };
+    positions.sort_by(|a, b| b.y.total_cmp(&a.y));
+    // A ruler just right of the selection, with a tick at every object.
+    let x = positions.iter().map(|p| p.x).fold(f32::MIN, f32::max) + 80.;
+    let colour = Color::rgb(0.2, 0.9, 1.);
+    let painter = label_painter(&mut contexts);
+    for pair in positions.windows(2) {
+        let (top, bottom) = (pair[0].y, pair[1].y);
+        gizmos.line_2d(Vec2::new(x, top), Vec2::new(x, bottom), colour);
+        gizmos.line_2d(Vec2::new(x - 6., top), Vec2::new(x + 6., top), colour);
+        gizmos.line_2d(Vec2::new(x - 6., bottom), Vec2::new(x + 6., bottom), colour);
+        if top - bottom < 1. {
+            continue;
+        }
+        paint_label(
+            &painter,
+            world_to_screen(window, camera, Vec2::new(x + 10., (top + bottom) / 2.)),
+            format!(
+                "{:.0} px, {:.2} s",
+                top - bottom,
+                fall_time(top - bottom, settings.fall_speed)
+            ),
+        );
+    }
+}
@@ -1,10 +1,22 @@
 use crate::{
-    structure_ui::{overlaps_ui, UISprite},
+    anchor::Anchor,
+    brush::{BrushMode, BrushSettings},
+    catalog,
+    grid::{snap_position, GridSettings, Guides},
+    layers::Locked,
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
     CombinedSheet, GameState,
 };
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
-use rand::random;
+use bevy_egui::{egui, EguiContexts};
+
+const SCROLL_SPEED: f32 = 40.;
+const ITEM_BASE_Z: f32 = 1.;
+const ITEM_Z_STEP: f32 = 0.05;
+const MARKER_BASE_Z: i32 = -1_000_000;
+const ERASE_STROKE_STEP: f32 = 5.;
 
 #[derive(Component)]
 pub struct EditorTool {
@@ -26,35 +38,258 @@ pub struct BuiltItem;
 
 #[derive(Component)]
 pub struct BuiltButton {
-    id: Entity,
+    pub id: Entity,
 }
 
 #[derive(Component)]
-struct Selected;
+pub struct Selected;
+
+/// Sent by the tools for every object the user placed, so other features can react to it.
+#[derive(Event)]
+pub struct ItemPlaced {
+    pub entity: Entity,
+    pub index: usize,
+    pub position: Vec2,
+    pub order: DrawOrder,
+}
+
+/// Position of an object in the stack of placed objects, higher is drawn on top.
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DrawOrder(pub u32);
+
+impl DrawOrder {
+    pub fn z(&self) -> f32 {
+        ITEM_BASE_Z + self.0 as f32 * ITEM_Z_STEP
+    }
+
+    fn marker_z(&self) -> i32 {
+        MARKER_BASE_Z + self.0 as i32
+    }
+
+    pub fn above<'a>(orders: impl Iterator<Item = &'a DrawOrder>) -> DrawOrder {
+        DrawOrder(orders.map(|order| order.0 + 1).max().unwrap_or(0))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EraseMode {
+    Stroke,
+    Rectangle,
+}
+
+#[derive(Resource)]
+pub struct EraserSettings {
+    pub mode: EraseMode,
+    pub show_markers: bool,
+}
+
+impl Default for EraserSettings {
+    fn default() -> Self {
+        EraserSettings {
+            mode: EraseMode::Stroke,
+            show_markers: true,
+        }
+    }
+}
+
+#[derive(Default)]
+struct EraseDrag {
+    start: Option<Vec2>,
+    last: Vec2,
+}
 
 pub struct MousePlugin;
 
 impl Plugin for MousePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_editor_tool)
+        app.add_event::<ItemPlaced>()
+            .add_systems(Startup, spawn_editor_tool)
             .add_systems(
                 Update,
                 spawn_selected_item.run_if(in_state(GameState::Building)),
             )
-            .add_systems(Update, erase_item.run_if(in_state(GameState::Erasing)))
-            .add_systems(Update, movement);
+            .init_resource::<EraserSettings>()
+            .add_systems(
+                Update,
+                (erase_item, erase_under_cursor, eraser_window)
+                    .run_if(in_state(GameState::Erasing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    movement,
+                    scroll_camera,
+                    sync_built_buttons,
+                    apply_draw_order,
+                ),
+            );
+    }
+}
+
+pub fn cursor_world_position(window: &Window, camera: &Transform) -> Option<Vec2> {
+    window.cursor_position().map(|position| {
+        Vec2::new(
+            position.x - (window.width() / 2.),
+            -position.y + (window.height() / 2.),
+        ) + camera.translation.truncate()
+    })
+}
+
+pub fn world_to_screen(window: &Window, camera: &Transform, position: Vec2) -> Vec2 {
+    let relative = position - camera.translation.truncate();
+    Vec2::new(
+        relative.x + (window.width() / 2.),
+        (window.height() / 2.) - relative.y,
+    )
+}
+
+/// Spawns a placed object together with the white button used to erase it.
+pub fn spawn_built_item(
+    commands: &mut Commands,
+    texture_atlas: &Handle<TextureAtlas>,
+    index: usize,
+    position: Vec2,
+    order: DrawOrder,
+) -> Entity {
+    let item = commands
+        .spawn(SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(index),
+            texture_atlas: texture_atlas.clone(),
+            transform: Transform::from_translation(position.extend(order.z())),
+            ..Default::default()
+        })
+        .insert(BuiltItem)
+        .insert(order)
+        .insert(ItemName {
+            name: catalog::name_of(index),
+        })
+        .id();
+
+    let z = order.marker_z();
+    commands
+        .spawn(ButtonBundle {
+            style: Style {
+                width: Val::Px(30.),
+                height: Val::Px(22.),
+                border: UiRect::all(Val::Px(1.)),
+                position_type: PositionType::Absolute,
+                display: Display::None,
+                ..Default::default()
+            },
+            z_index: ZIndex::Global(z),
+            border_color: Color::BLACK.into(),
+            ..Default::default()
+        })
+        .insert(LocalZ { z })
+        .insert(BuiltButton { id: item });
+
+    item
+}
+
+/// The sprite index and the exported name always have to change together.
+pub fn change_item_type(sprite: &mut TextureAtlasSprite, name: &mut ItemName, index: usize) {
+    if sprite.index != index {
+        sprite.index = index;
+        name.name = catalog::name_of(index);
+    }
+}
+
+pub fn hit_test(trans: &Transform, index: usize, point: Vec2) -> bool {
+    let half = catalog::size_of(index) * trans.scale.truncate().abs() / 2.;
+    let offset = (point - trans.translation.truncate()).abs();
+    offset.x <= half.x && offset.y <= half.y
+}
+
+/// The object drawn on top at the given point.
+pub fn topmost_item<'a>(
+    point: Vec2,
+    items: impl Iterator<Item = (Entity, &'a Transform, &'a TextureAtlasSprite)>,
+) -> Option<Entity> {
+    items
+        .filter(|(_, trans, sprite)| hit_test(trans, sprite.index, point))
+        .max_by(|(_, a, _), (_, b, _)| a.translation.z.total_cmp(&b.translation.z))
+        .map(|(entity, _, _)| entity)
+}
+
+/// Buttons are screen space UI nodes, so they are repositioned over their objects every frame.
+/// Buttons whose object is gone are removed here, so erasing only has to despawn the object.
+fn sync_built_buttons(
+    mut commands: Commands,
+    mut button_q: Query<(Entity, &BuiltButton, &mut Style)>,
+    item_q: Query<(&Transform, Option<&Locked>), With<BuiltItem>>,
+    camera_q: Query<&Transform, With<Camera>>,
+    windows_q: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<EraserSettings>,
+) {
+    let window = windows_q.single();
+    let camera = camera_q.single();
+    for (entity, button, mut style) in button_q.iter_mut() {
+        let Ok((trans, locked)) = item_q.get(button.id) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let screen = world_to_screen(window, camera, trans.translation.truncate());
+        style.left = Val::Px(screen.x - 15.);
+        style.top = Val::Px(screen.y - 11.);
+        style.display = if settings.show_markers && locked.is_none() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+/// Keeps sprite depth and marker stacking in line with the draw order.
+fn apply_draw_order(
+    mut item_q: Query<(&DrawOrder, &mut Transform), With<BuiltItem>>,
+    mut button_q: Query<(&BuiltButton, &mut ZIndex, &mut LocalZ)>,
+) {
+    for (order, mut trans) in item_q.iter_mut() {
+        if trans.translation.z != order.z() {
+            trans.translation.z = order.z();
+        }
+    }
+    for (button, mut z_index, mut local_z) in button_q.iter_mut() {
+        let Ok((order, _)) = item_q.get(button.id) else {
+            continue;
+        };
+        if local_z.z != order.marker_z() {
+            local_z.z = order.marker_z();
+            *z_index = ZIndex::Global(local_z.z);
+        }
+    }
+}
+
+fn scroll_camera(
+    mut wheel: EventReader<MouseWheel>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+    over_egui: Res<PointerOverEgui>,
+) {
+    let mut delta = 0.;
+    for event in wheel.iter() {
+        delta += match event.unit {
+            MouseScrollUnit::Line => event.y * SCROLL_SPEED,
+            MouseScrollUnit::Pixel => event.y,
+        };
+    }
+    if delta == 0. || over_egui.0 {
+        return;
+    }
+    for mut trans in camera_q.iter_mut() {
+        trans.translation.y += delta;
     }
 }
 
 fn movement(
-    mut editor_tool_query: Query<&mut Transform, With<EditorTool>>,
+    mut editor_tool_query: Query<&mut Transform, (With<EditorTool>, Without<Camera>)>,
     windows_q: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<&Transform, With<Camera>>,
 ) {
     for mut transform in editor_tool_query.iter_mut() {
         let window = windows_q.single();
-        if let Some(position) = window.cursor_position() {
-            transform.translation.x = position.x - (window.width() / 2.);
-            transform.translation.y = -1. * position.y + (window.height() / 2.);
+        if let Some(position) = cursor_world_position(window, camera_q.single()) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
         }
     }
 }
@@ -84,68 +319,49 @@ fn spawn_selected_item(
     mut commands: Commands,
     texture_atlas: Res<CombinedSheet>,
     mut editor_tool_q: Query<(&TextureAtlasSprite, &Transform, &mut EditorTool), With<EditorTool>>,
+    order_q: Query<&DrawOrder>,
     buttons: Res<Input<MouseButton>>,
     ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
-    windows_q: Query<&Window, With<PrimaryWindow>>,
+    anchor: Res<Anchor>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    brush: Res<BrushSettings>,
+    mut placed: EventWriter<ItemPlaced>,
 ) {
+    if brush.mode != BrushMode::Single {
+        // A press that started painting must not place an object when switching back mid-drag.
+        for (_, _, mut tool) in editor_tool_q.iter_mut() {
+            tool.is_left_clicked = buttons.pressed(MouseButton::Left);
+        }
+        return;
+    }
     for (sprite, trans, mut tool) in editor_tool_q.iter_mut() {
         if !buttons.pressed(MouseButton::Left) {
             tool.is_left_clicked = false;
             return;
         }
-        let editor_is_on_ui = overlaps_ui(trans, &ui_q);
-        if tool.is_left_clicked || editor_is_on_ui {
+        let editor_is_on_ui = overlaps_ui(trans, &ui_q) || over_egui.0;
+        let editor_is_on_anchor = anchor.dragging || anchor.grabs(trans.translation.truncate());
+        if tool.is_left_clicked || editor_is_on_ui || editor_is_on_anchor {
             return;
         }
 
-        let mut new_trans = trans.clone();
-        new_trans.translation.z = trans.translation.z - (random::<f32>() * 100.) + 1.;
-        let name;
-        match sprite.index {
-            0 => name = "blackhole",
-            1 => name = "rainbow",
-            2 => name = "energybar",
-            3 => name = "regular",
-            4 => name = "plane",
-            5 => name = "planet",
-            _ => name = "",
-        }
-        let item = commands
-            .spawn(SpriteSheetBundle {
-                sprite: sprite.clone(),
-                texture_atlas: texture_atlas.0.clone(),
-                transform: new_trans,
-                ..Default::default()
-            })
-            .insert(BuiltItem)
-            .insert(ItemName { name })
-            .id();
-
-        let window = windows_q.single();
-        if let Some(position) = window.cursor_position() {
-            let z = i32::abs(random::<i32>()) * -1 - 1;
-            let button = commands
-                .spawn(ButtonBundle {
-                    style: Style {
-                        width: Val::Px(30.),
-                        height: Val::Px(22.),
-                        left: Val::Px(position.x - 15.),
-                        top: Val::Px(position.y - 11.),
-                        border: UiRect::all(Val::Px(1.)),
-                        position_type: PositionType::Absolute,
-                        ..Default::default()
-                    },
-                    z_index: ZIndex::Global(z),
-                    border_color: Color::BLACK.into(),
-                    ..Default::default()
-                })
-                .insert(LocalZ { z: z })
-                .insert(BuiltButton { id: item })
-                .id();
-            commands.entity(button);
-        }
-
-        commands.entity(item);
+        let position = snap_position(&grid, &guides, trans.translation.truncate());
+        let order = DrawOrder::above(order_q.iter());
+        let entity = spawn_built_item(
+            &mut commands,
+            &texture_atlas.0,
+            sprite.index,
+            position,
+            order,
+        );
+        placed.send(ItemPlaced {
+            entity,
+            index: sprite.index,
+            position,
+            order,
+        });
 
         tool.is_left_clicked = true;
     }
@@ -178,3 +394,91 @@ fn erase_item(
         commands.entity(max_z_sprite.unwrap()).despawn();
     }
 }
+
+fn erase_under_cursor(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    editor_tool_q: Query<&Transform, With<EditorTool>>,
+    item_q: Query<(Entity, &Transform, &TextureAtlasSprite), (With<BuiltItem>, Without<Locked>)>,
+    marker_q: Query<&Interaction, With<BuiltButton>>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    over_egui: Res<PointerOverEgui>,
+    settings: Res<EraserSettings>,
+    mut drag: Local<EraseDrag>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    let cursor = tool.translation.truncate();
+
+    if buttons.just_pressed(MouseButton::Left) {
+        if overlaps_ui(tool, &ui_q) || over_egui.0 {
+            return;
+        }
+        drag.start = Some(cursor);
+        drag.last = cursor;
+        // Clicks on the white markers are handled by erase_item.
+        if settings.mode == EraseMode::Stroke
+            && !marker_q.iter().any(|i| *i == Interaction::Pressed)
+        {
+            if let Some(item) = topmost_item(cursor, item_q.iter()) {
+                commands.entity(item).despawn();
+            }
+        }
+        return;
+    }
+    let Some(start) = drag.start else {
+        return;
+    };
+
+    match settings.mode {
+        EraseMode::Stroke => {
+            if !buttons.pressed(MouseButton::Left) {
+                drag.start = None;
+                return;
+            }
+            if marker_q.iter().any(|i| *i == Interaction::Pressed) {
+                drag.last = cursor;
+                return;
+            }
+            let steps = (drag.last.distance(cursor) / ERASE_STROKE_STEP).ceil() as usize;
+            let mut erased = Vec::new();
+            for step in 1..=steps {
+                let point = drag.last.lerp(cursor, step as f32 / steps as f32);
+                let remaining = item_q.iter().filter(|(e, _, _)| !erased.contains(e));
+                if let Some(item) = topmost_item(point, remaining) {
+                    commands.entity(item).despawn();
+                    erased.push(item);
+                }
+            }
+            drag.last = cursor;
+        }
+        EraseMode::Rectangle => {
+            let rect = Rect::from_corners(start, cursor);
+            if buttons.pressed(MouseButton::Left) {
+                gizmos.rect_2d(rect.center(), 0., rect.size(), Color::RED);
+                return;
+            }
+            for (entity, trans, _) in item_q.iter() {
+                if rect.contains(trans.translation.truncate()) {
+                    commands.entity(entity).despawn();
+                }
+            }
+            drag.start = None;
+        }
+    }
+}
+
+fn eraser_window(mut settings: ResMut<EraserSettings>, mut contexts: EguiContexts) {
+    egui::Window::new("Eraser")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.mode, EraseMode::Stroke, "Click and drag");
+                ui.selectable_value(&mut settings.mode, EraseMode::Rectangle, "Rectangle");
+            });
+            ui.checkbox(&mut settings.show_markers, "Show white object markers");
+        });
+}
@@ -0,0 +1,283 @@
+use crate::{
+    catalog::{self, ObjectRole},
+    layers::Locked,
+    mouse::{BuiltItem, DrawOrder},
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// Relaxing stops after this many rounds even if some objects still overlap.
+const RELAX_ROUNDS: usize = 100;
+/// Pushed a little further than needed, so rounding doesn't leave them overlapping by a hair.
+const RELAX_MARGIN: f32 = 0.05;
+
+/// Items of the same type as an earlier one and less than a pixel away from it on both axes.
+/// Compared in the editor rather than in exported whole pixels, which depend on where the anchor is.
+pub fn exact_duplicates(items: &[(usize, Vec2)]) -> Vec<usize> {
+    let mut duplicates = Vec::new();
+    for (i, (index, position)) in items.iter().enumerate() {
+        let twin = items[..i]
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| !duplicates.contains(j))
+            .any(|(_, (other, at))| other == index && (*at - *position).abs().max_element() < 1.);
+        if twin {
+            duplicates.push(i);
+        }
+    }
+    duplicates
+}
+
+/// Pairs of boxes, given as centre and size, that are closer than `spacing` on both axes.
+fn overlapping_pairs(boxes: &[(Vec2, Vec2)], spacing: f32) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (i, (a, a_size)) in boxes.iter().enumerate() {
+        for (j, (b, b_size)) in boxes.iter().enumerate().skip(i + 1) {
+            let reach = (*a_size + *b_size) / 2. + spacing;
+            let offset = (*b - *a).abs();
+            if offset.x < reach.x && offset.y < reach.y {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// Pushes overlapping boxes away from each other along the line between them, so the layout keeps its shape.
+/// Boxes that can't move only push the others.
+fn relax(boxes: &mut [(Vec2, Vec2)], movable: &[bool], spacing: f32) {
+    for _ in 0..RELAX_ROUNDS {
+        let pairs = overlapping_pairs(boxes, spacing);
+        if pairs.is_empty() {
+            return;
+        }
+        for (i, j) in pairs {
+            let (a, a_size) = boxes[i];
+            let (b, b_size) = boxes[j];
+            let reach = (a_size + b_size) / 2. + spacing;
+            // Boxes at the same spot are spread out in a fixed, evenly turning direction.
+            let direction = (b - a)
+                .try_normalize()
+                .unwrap_or_else(|| Vec2::from_angle(j as f32 * 2.4));
+            // Distance along the direction at which the boxes stop overlapping on one of the axes.
+            let needed = (reach.x / direction.x.abs()).min(reach.y / direction.y.abs());
+            let push = direction * (needed + RELAX_MARGIN - (b - a).length()).max(0.);
+            match (movable[i], movable[j]) {
+                (true, true) => {
+                    boxes[i].0 -= push / 2.;
+                    boxes[j].0 += push / 2.;
+                }
+                (true, false) => boxes[i].0 -= push,
+                (false, true) => boxes[j].0 += push,
+                (false, false) => {}
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+struct OverlapSettings {
+    highlight: bool,
+    spacing: f32,
+}
+
+impl Default for OverlapSettings {
+    fn default() -> Self {
+        OverlapSettings {
+            highlight: true,
+            spacing: 10.,
+        }
+    }
+}
+
+pub struct OverlapPlugin;
+
+impl Plugin for OverlapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverlapSettings>()
+            .add_systems(Update, overlap_window);
+    }
+}
+
+fn overlap_window(
+    mut commands: Commands,
+    mut item_q: Query<
+        (
+            Entity,
+            &mut Transform,
+            &TextureAtlasSprite,
+            &DrawOrder,
+            &Visibility,
+            Option<&Locked>,
+        ),
+        With<BuiltItem>,
+    >,
+    mut settings: ResMut<OverlapSettings>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    // From the back to the front, so the one drawn at the back is kept.
+    let mut items: Vec<(Entity, usize, Vec2, Vec2, DrawOrder, bool)> = item_q
+        .iter()
+        .filter(|(_, _, _, _, visibility, _)| **visibility != Visibility::Hidden)
+        .map(|(entity, trans, sprite, order, _, locked)| {
+            let size = catalog::size_of(sprite.index) * trans.scale.truncate().abs();
+            let position = trans.translation.truncate();
+            (
+                entity,
+                sprite.index,
+                position,
+                size,
+                *order,
+                locked.is_none(),
+            )
+        })
+        .collect();
+    items.sort_by_key(|item| item.4);
+    let duplicates = exact_duplicates(
+        &items
+            .iter()
+            .map(|item| (item.1, item.2))
+            .collect::<Vec<_>>(),
+    );
+    // Plane sensors are invisible in the game, anything may cross them.
+    let solid: Vec<usize> = (0..items.len())
+        .filter(|i| catalog::role_of(items[*i].1) != ObjectRole::Sensor)
+        .collect();
+    let mut boxes: Vec<(Vec2, Vec2)> = solid.iter().map(|i| (items[*i].2, items[*i].3)).collect();
+    let locked_duplicates = duplicates.iter().filter(|i| !items[**i].5).count();
+    let overlaps = overlapping_pairs(&boxes, 0.);
+
+    let mut remove = false;
+    let mut relaxing = false;
+    egui::Window::new("Overlaps")
+        .default_pos(egui::pos2(660., 240.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!(
+                "{} overlapping pairs, {} exact duplicates",
+                overlaps.len(),
+                duplicates.len()
+            ));
+            if locked_duplicates > 0 {
+                ui.label(format!(
+                    "{} of the duplicates are locked and won't be removed",
+                    locked_duplicates
+                ));
+            }
+            ui.checkbox(&mut settings.highlight, "Highlight on the canvas");
+            remove = ui
+                .add_enabled(
+                    !duplicates.is_empty(),
+                    egui::Button::new("Remove exact duplicates"),
+                )
+                .on_hover_text("Keeps the one drawn at the back")
+                .clicked();
+            ui.horizontal(|ui| {
+                relaxing = ui
+                    .add_enabled(!overlaps.is_empty(), egui::Button::new("Relax overlaps"))
+                    .on_hover_text("Pushes overlapping objects apart, locked objects stay")
+                    .clicked();
+                ui.label("Spacing:");
+                ui.add(egui::DragValue::new(&mut settings.spacing).clamp_range(0..=500));
+            });
+        });
+
+    if remove {
+        for i in duplicates.iter() {
+            if items[*i].5 {
+                commands.entity(items[*i].0).despawn();
+            }
+        }
+        return;
+    }
+    if relaxing {
+        let movable: Vec<bool> = solid.iter().map(|i| items[*i].5).collect();
+        relax(&mut boxes, &movable, settings.spacing);
+        for (i, (position, _)) in solid.iter().zip(boxes.iter()) {
+            if *position == items[*i].2 {
+                continue;
+            }
+            if let Ok((_, mut trans, _, _, _, _)) = item_q.get_mut(items[*i].0) {
+                trans.translation.x = position.x;
+                trans.translation.y = position.y;
+            }
+        }
+        return;
+    }
+
+    if !settings.highlight {
+        return;
+    }
+    for (i, j) in overlaps.iter() {
+        for k in [solid[*i], solid[*j]] {
+            gizmos.rect_2d(items[k].2, 0., items[k].3, Color::ORANGE);
+        }
+    }
+    for i in duplicates.iter() {
+        gizmos.rect_2d(items[*i].2, 0., items[*i].3 + 8., Color::RED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_are_the_later_copies_of_the_same_type() {
+        let items = [
+            (3, Vec2::new(0., 0.)),
+            (3, Vec2::new(0.5, -0.4)),
+            (2, Vec2::new(0., 0.)),
+            (3, Vec2::new(1.5, 0.)),
+            (3, Vec2::new(0.2, 0.2)),
+        ];
+        assert_eq!(exact_duplicates(&items), vec![1, 4]);
+    }
+
+    #[test]
+    fn pairs_overlap_only_closer_than_the_spacing() {
+        let size = Vec2::splat(100.);
+        let boxes = [
+            (Vec2::new(0., 0.), size),
+            (Vec2::new(90., 0.), size),
+            (Vec2::new(0., 105.), size),
+            (Vec2::new(500., 500.), size),
+        ];
+        assert_eq!(overlapping_pairs(&boxes, 0.), vec![(0, 1)]);
+        assert_eq!(overlapping_pairs(&boxes, 10.), vec![(0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn relaxing_leaves_no_overlap() {
+        let size = Vec2::new(100., 50.);
+        let mut boxes: Vec<(Vec2, Vec2)> = (0..12)
+            .map(|i| (Vec2::new((i % 4) as f32 * 30., (i / 4) as f32 * 20.), size))
+            .collect();
+        relax(&mut boxes, &[true; 12], 10.);
+        assert!(overlapping_pairs(&boxes, 10.).is_empty());
+    }
+
+    #[test]
+    fn relaxing_spreads_boxes_at_the_same_spot() {
+        let size = Vec2::splat(100.);
+        let mut boxes = vec![(Vec2::ZERO, size); 3];
+        relax(&mut boxes, &[true; 3], 0.);
+        assert!(overlapping_pairs(&boxes, 0.).is_empty());
+        assert_ne!(boxes[0].0, boxes[1].0);
+    }
+
+    #[test]
+    fn relaxing_keeps_locked_boxes() {
+        let size = Vec2::splat(100.);
+        let mut boxes = vec![
+            (Vec2::ZERO, size),
+            (Vec2::new(20., 10.), size),
+            (Vec2::new(-30., 0.), size),
+        ];
+        relax(&mut boxes, &[false, true, true], 5.);
+        assert_eq!(boxes[0].0, Vec2::ZERO);
+        assert!(overlapping_pairs(&boxes, 5.).is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+use crate::{
+    cache::{Cached, StructureVersion},
+    catalog::{self, ObjectRole},
+    mouse::BuiltItem,
+    play::PlaySettings,
+    PLAY_COLUMN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// Height of one step of the analysis, in pixels of the fall.
+const ROW_HEIGHT: f32 = 4.;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChokeReason {
+    /// A gap between damaging objects the player doesn't fit through.
+    TooNarrow,
+    /// There is a way through, but the player can't move sideways fast enough to reach it.
+    TooFast,
+    /// Damaging objects cover the whole play column.
+    Wall,
+}
+
+impl ChokeReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChokeReason::TooNarrow => "gap narrower than the player",
+            ChokeReason::TooFast => "can't be reached in time",
+            ChokeReason::Wall => "no gap at all",
+        }
+    }
+}
+
+pub struct Choke {
+    pub reason: ChokeReason,
+    /// Centre height of the player.
+    pub y: f32,
+    pub from: f32,
+    pub to: f32,
+}
+
+#[derive(Default)]
+pub struct Analysis {
+    pub passable: bool,
+    pub chokes: Vec<Choke>,
+    /// Every height with the ranges of positions the player's centre can be at there.
+    pub reachable: Vec<(f32, Vec<(f32, f32)>)>,
+}
+
+/// The player's hitbox and how fast it moves sideways compared to the fall.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Player {
+    pub hitbox: Vec2,
+    pub speed: f32,
+    pub fall_speed: f32,
+}
+
+impl From<&PlaySettings> for Player {
+    fn from(settings: &PlaySettings) -> Self {
+        Player {
+            hitbox: settings.hitbox,
+            speed: settings.player_speed,
+            fall_speed: settings.fall_speed,
+        }
+    }
+}
+
+/// Removes `(from, to)` from a sorted list of disjoint ranges.
+fn subtract(ranges: &[(f32, f32)], from: f32, to: f32) -> Vec<(f32, f32)> {
+    let mut result = Vec::new();
+    for &(start, end) in ranges {
+        if to <= start || from >= end {
+            result.push((start, end));
+            continue;
+        }
+        if from > start {
+            result.push((start, from));
+        }
+        if to < end {
+            result.push((to, end));
+        }
+    }
+    result
+}
+
+fn intersect(a: &[(f32, f32)], b: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut result = Vec::new();
+    for &(a_start, a_end) in a {
+        for &(b_start, b_end) in b {
+            let (start, end) = (a_start.max(b_start), a_end.min(b_end));
+            if start <= end {
+                result.push((start, end));
+            }
+        }
+    }
+    result
+}
+
+/// Grows every range by `amount` on both sides, merging the ones that start to overlap.
+fn widen(ranges: &[(f32, f32)], amount: f32) -> Vec<(f32, f32)> {
+    let mut result: Vec<(f32, f32)> = Vec::new();
+    for &(start, end) in ranges {
+        let (start, end) = (start - amount, end + amount);
+        match result.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => result.push((start, end)),
+        }
+    }
+    result
+}
+
+/// Walks down through the structure and keeps track of where the player can be without touching a damaging object.
+pub fn analyse(items: &[(usize, Vec2)], player: Player) -> Analysis {
+    let hazards: Vec<(Vec2, Vec2)> = items
+        .iter()
+        .filter(|(index, _)| catalog::role_of(*index) == ObjectRole::Hazard)
+        .map(|(index, position)| (*position, catalog::size_of(*index)))
+        .collect();
+    let Some(top) = hazards
+        .iter()
+        .map(|(p, size)| p.y + size.y / 2.)
+        .reduce(f32::max)
+    else {
+        return Analysis {
+            passable: true,
+            ..Default::default()
+        };
+    };
+    let bottom = hazards
+        .iter()
+        .map(|(p, size)| p.y - size.y / 2.)
+        .fold(top, f32::min);
+
+    let half = player.hitbox / 2.;
+    let column = PLAY_COLUMN_WIDTH / 2.;
+    let centre_range = vec![(-column + half.x, column - half.x)];
+    // Sideways distance the player covers while falling one row.
+    let slack = player.speed / player.fall_speed.max(1.) * ROW_HEIGHT;
+
+    let mut analysis = Analysis {
+        passable: true,
+        ..Default::default()
+    };
+    let mut reachable = centre_range.clone();
+    let mut y = top + half.y;
+    let mut narrow_before: Vec<(f32, f32)> = Vec::new();
+    while y >= bottom - half.y {
+        let overlapping: Vec<&(Vec2, Vec2)> = hazards
+            .iter()
+            .filter(|(p, size)| (p.y - y).abs() < size.y / 2. + half.y)
+            .collect();
+
+        let mut free = centre_range.clone();
+        let mut gaps = vec![(-column, column)];
+        for (p, size) in overlapping.iter() {
+            free = subtract(
+                &free,
+                p.x - size.x / 2. - half.x,
+                p.x + size.x / 2. + half.x,
+            );
+            gaps = subtract(&gaps, p.x - size.x / 2., p.x + size.x / 2.);
+        }
+        // The strip between a hazard and the column edge is wall, not a way through.
+        let narrow: Vec<(f32, f32)> = gaps
+            .into_iter()
+            .filter(|&(start, end)| start > -column && end < column)
+            .filter(|(start, end)| end - start < player.hitbox.x)
+            .collect();
+        // A narrow gap is reported once where it starts, not on every row it spans.
+        for &(from, to) in narrow.iter() {
+            if intersect(&narrow_before, &[(from, to)]).is_empty() {
+                analysis.chokes.push(Choke {
+                    reason: ChokeReason::TooNarrow,
+                    y,
+                    from,
+                    to,
+                });
+            }
+        }
+        narrow_before = narrow;
+
+        reachable = intersect(&widen(&reachable, slack), &free);
+        if reachable.is_empty() {
+            analysis.passable = false;
+            let (reason, from, to) = match (free.first(), free.last()) {
+                (Some(first), Some(last)) => (ChokeReason::TooFast, first.0, last.1),
+                _ => (ChokeReason::Wall, -column, column),
+            };
+            analysis.chokes.push(Choke {
+                reason,
+                y,
+                from,
+                to,
+            });
+            break;
+        }
+        analysis.reachable.push((y, reachable.clone()));
+        y -= ROW_HEIGHT;
+    }
+    analysis
+}
+
+#[derive(Resource, Default)]
+struct PassabilityOverlay {
+    visible: bool,
+}
+
+#[derive(Resource, Default)]
+struct PassabilityCache(Cached<(u64, Player), Analysis>);
+
+pub struct PassabilityPlugin;
+
+impl Plugin for PassabilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PassabilityOverlay>()
+            .init_resource::<PassabilityCache>()
+            .add_systems(Update, passability_window);
+    }
+}
+
+fn passability_window(
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    version: Res<StructureVersion>,
+    mut cache: ResMut<PassabilityCache>,
+    mut settings: ResMut<PlaySettings>,
+    mut overlay: ResMut<PassabilityOverlay>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    let key = (version.0, Player::from(&*settings));
+    // Only worked out while the window is open or the overlay is shown.
+    let analyse_items = || {
+        let items: Vec<(usize, Vec2)> = item_q
+            .iter()
+            .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+            .map(|(trans, sprite, _)| (sprite.index, trans.translation.truncate()))
+            .collect();
+        analyse(&items, key.1)
+    };
+    egui::Window::new("Passability")
+        .default_pos(egui::pos2(660., 15.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Player size:");
+                ui.add(egui::DragValue::new(&mut settings.hitbox.x).clamp_range(1..=500));
+                ui.add(egui::DragValue::new(&mut settings.hitbox.y).clamp_range(1..=500));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Sideways speed:");
+                ui.add(egui::DragValue::new(&mut settings.player_speed).clamp_range(0..=3000));
+                ui.label("Fall speed:");
+                ui.add(egui::DragValue::new(&mut settings.fall_speed).clamp_range(10..=3000));
+            });
+            ui.checkbox(&mut overlay.visible, "Show on the canvas");
+            let analysis = cache.0.get(key, &analyse_items);
+            if analysis.passable {
+                ui.label("The player can get through.");
+            } else {
+                ui.colored_label(egui::Color32::RED, "The player can't get through!");
+            }
+            for choke in analysis.chokes.iter() {
+                ui.label(format!("y {:.0}: {}", choke.y, choke.reason.label()));
+            }
+        });
+
+    if !overlay.visible {
+        return;
+    }
+    let analysis = cache.0.get(key, analyse_items);
+    let corridor = Color::rgba(0.1, 0.8, 0.2, 0.25);
+    for (y, ranges) in analysis.reachable.iter() {
+        for (from, to) in ranges {
+            gizmos.line_2d(Vec2::new(*from, *y), Vec2::new(*to, *y), corridor);
+        }
+    }
+    for choke in analysis.chokes.iter() {
+        let colour = match choke.reason {
+            ChokeReason::TooNarrow => Color::ORANGE,
+            _ => Color::RED,
+        };
+        let centre = Vec2::new((choke.from + choke.to) / 2., choke.y);
+        let size = Vec2::new((choke.to - choke.from).max(4.), key.1.hitbox.y);
+        gizmos.rect_2d(centre, 0., size, colour);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Player {
+        Player::from(&PlaySettings::default())
+    }
+
+    #[test]
+    fn gap_narrower_than_the_player_is_a_choke() {
+        // Two enemies 100 px wide leave 40 px between them.
+        let regular = catalog::index_of("regular").unwrap();
+        let items = [
+            (regular, Vec2::new(-70., 0.)),
+            (regular, Vec2::new(70., 0.)),
+        ];
+        let analysis = analyse(&items, player());
+        let narrow: Vec<&Choke> = analysis
+            .chokes
+            .iter()
+            .filter(|choke| choke.reason == ChokeReason::TooNarrow)
+            .collect();
+        assert_eq!(narrow.len(), 1);
+        assert_eq!((narrow[0].from, narrow[0].to), (-20., 20.));
+        // The player still gets around them.
+        assert!(analysis.passable);
+    }
+
+    #[test]
+    fn gap_wider_than_the_player_is_fine() {
+        let regular = catalog::index_of("regular").unwrap();
+        let items = [
+            (regular, Vec2::new(-100., 0.)),
+            (regular, Vec2::new(100., 0.)),
+        ];
+        let analysis = analyse(&items, player());
+        assert!(analysis.chokes.is_empty());
+        assert!(analysis.passable);
+    }
+
+    #[test]
+    fn strip_along_the_column_edge_is_no_choke() {
+        // Leaves 20 px between the enemy and the right edge of the column.
+        let regular = catalog::index_of("regular").unwrap();
+        let right = PLAY_COLUMN_WIDTH / 2. - 20. - catalog::size_of(regular).x / 2.;
+        let analysis = analyse(&[(regular, Vec2::new(right, 0.))], player());
+        assert!(analysis.chokes.is_empty());
+        assert!(analysis.passable);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    catalog,
+    ghost::GhostPreview,
+    grid::{snap_position, GridSettings, Guides},
+    mouse::{spawn_built_item, DrawOrder, EditorTool, ItemPlaced},
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
+    CombinedSheet, GameState,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// How close the cursor has to be to grab a point of the path.
+const GRAB_RADIUS: f32 = 10.;
+/// Straight segments each cubic piece of a Bezier path is approximated with.
+const BEZIER_STEPS: usize = 32;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PathCurve {
+    Polyline,
+    Bezier,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PathFill {
+    Spacing,
+    Count,
+}
+
+/// The path being drawn, it can be changed freely until it is baked into objects.
+#[derive(Resource)]
+pub struct PathSettings {
+    pub points: Vec<Vec2>,
+    pub curve: PathCurve,
+    pub fill: PathFill,
+    pub object: usize,
+    pub spacing: f32,
+    pub count: usize,
+    dragging: Option<usize>,
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        PathSettings {
+            points: Vec::new(),
+            curve: PathCurve::Polyline,
+            fill: PathFill::Spacing,
+            object: 1,
+            spacing: 80.,
+            count: 10,
+            dragging: None,
+        }
+    }
+}
+
+impl PathSettings {
+    /// The path as a list of straight segments.
+    fn outline(&self) -> Vec<Vec2> {
+        match self.curve {
+            PathCurve::Polyline => self.points.clone(),
+            PathCurve::Bezier if self.points.len() > 2 => {
+                let mut outline = vec![self.points[0]];
+                for i in 0..self.points.len() - 1 {
+                    let piece = bezier_piece(&self.points, i);
+                    outline.extend(
+                        (1..=BEZIER_STEPS)
+                            .map(|step| cubic_point(piece, step as f32 / BEZIER_STEPS as f32)),
+                    );
+                }
+                outline
+            }
+            PathCurve::Bezier => self.points.clone(),
+        }
+    }
+
+    /// Where the objects go, measured along the path.
+    pub fn positions(&self) -> Vec<Vec2> {
+        let outline = self.outline();
+        let Some(first) = outline.first() else {
+            return Vec::new();
+        };
+        let length: f32 = outline.windows(2).map(|w| w[0].distance(w[1])).sum();
+        let distances: Vec<f32> = match self.fill {
+            PathFill::Spacing => {
+                let spacing = self.spacing.max(1.);
+                (0..=(length / spacing) as usize)
+                    .map(|i| i as f32 * spacing)
+                    .collect()
+            }
+            PathFill::Count if self.count < 2 => vec![0.; self.count],
+            PathFill::Count => (0..self.count)
+                .map(|i| length * i as f32 / (self.count - 1) as f32)
+                .collect(),
+        };
+
+        let mut positions = Vec::with_capacity(distances.len());
+        let mut segments = outline.windows(2);
+        let mut walked = 0.;
+        let mut segment = segments.next();
+        for distance in distances {
+            while let Some([start, end]) = segment {
+                let segment_length = start.distance(*end);
+                if distance <= walked + segment_length {
+                    let t = (distance - walked) / segment_length.max(f32::EPSILON);
+                    positions.push(start.lerp(*end, t));
+                    break;
+                }
+                walked += segment_length;
+                segment = segments.next();
+            }
+            if segment.is_none() {
+                positions.push(*outline.last().unwrap_or(first));
+            }
+        }
+        positions
+    }
+}
+
+/// The cubic piece from point `i` to the next one. Its handles follow the neighbouring points like a Catmull-Rom spline,
+/// so the pieces join smoothly and the path goes through every point however many there are.
+fn bezier_piece(points: &[Vec2], i: usize) -> [Vec2; 4] {
+    let before = points[i.saturating_sub(1)];
+    let after = points[(i + 2).min(points.len() - 1)];
+    let (start, end) = (points[i], points[i + 1]);
+    [
+        start,
+        start + (end - before) / 6.,
+        end - (after - start) / 6.,
+        end,
+    ]
+}
+
+fn cubic_point([a, b, c, d]: [Vec2; 4], t: f32) -> Vec2 {
+    let u = 1. - t;
+    a * u * u * u + b * 3. * u * u * t + c * 3. * u * t * t + d * t * t * t
+}
+
+pub struct PathPlugin;
+
+impl Plugin for PathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathSettings>().add_systems(
+            Update,
+            (path_window, edit_path, draw_path)
+                .chain()
+                .run_if(in_state(GameState::Pathing)),
+        );
+    }
+}
+
+/// Left click adds a point or grabs an existing one, right click removes it.
+fn edit_path(
+    buttons: Res<Input<MouseButton>>,
+    editor_tool_q: Query<&Transform, With<EditorTool>>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    mut path: ResMut<PathSettings>,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    let cursor = tool.translation.truncate();
+    if let Some(i) = path.dragging {
+        if buttons.pressed(MouseButton::Left) {
+            path.points[i] = snap_position(&grid, &guides, cursor);
+        } else {
+            path.dragging = None;
+        }
+        return;
+    }
+    if overlaps_ui(tool, &ui_q) || over_egui.0 {
+        return;
+    }
+    let grabbed = path
+        .points
+        .iter()
+        .position(|point| point.distance(cursor) <= GRAB_RADIUS);
+    if buttons.just_pressed(MouseButton::Left) {
+        path.dragging = Some(grabbed.unwrap_or_else(|| {
+            path.points.push(snap_position(&grid, &guides, cursor));
+            path.points.len() - 1
+        }));
+    } else if buttons.just_pressed(MouseButton::Right) {
+        if let Some(i) = grabbed {
+            path.points.remove(i);
+        }
+    }
+}
+
+fn draw_path(path: Res<PathSettings>, mut preview: ResMut<GhostPreview>, mut gizmos: Gizmos) {
+    let colour = Color::rgb(1., 0.5, 0.);
+    if path.curve == PathCurve::Bezier {
+        for w in path.points.windows(2) {
+            gizmos.line_2d(w[0], w[1], Color::rgba(1., 0.5, 0., 0.3));
+        }
+    }
+    for w in path.outline().windows(2) {
+        gizmos.line_2d(w[0], w[1], colour);
+    }
+    for point in path.points.iter() {
+        gizmos.circle_2d(*point, GRAB_RADIUS / 2., colour);
+    }
+    preview.0.extend(
+        path.positions()
+            .into_iter()
+            .map(|position| (path.object, position)),
+    );
+}
+
+fn path_window(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    order_q: Query<&DrawOrder>,
+    mut path: ResMut<PathSettings>,
+    mut placed: EventWriter<ItemPlaced>,
+    mut contexts: EguiContexts,
+) {
+    egui::Window::new("Path")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let path = &mut *path;
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut path.curve, PathCurve::Polyline, "Polyline");
+                ui.selectable_value(&mut path.curve, PathCurve::Bezier, "Bezier");
+            });
+            egui::ComboBox::from_label("Object")
+                .selected_text(catalog::name_of(path.object))
+                .show_ui(ui, |ui| {
+                    for (i, object) in catalog::OBJECTS.iter().enumerate() {
+                        ui.selectable_value(&mut path.object, i, object.name);
+                    }
+                });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut path.fill, PathFill::Spacing, "Spacing:");
+                ui.add(egui::DragValue::new(&mut path.spacing).clamp_range(1..=2000));
+            });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut path.fill, PathFill::Count, "Count:");
+                ui.add(egui::DragValue::new(&mut path.count).clamp_range(1..=500));
+            });
+            ui.label("Click to add points, drag them to move and right click to remove them.");
+            ui.horizontal(|ui| {
+                if ui.button("Bake").clicked() {
+                    let first = DrawOrder::above(order_q.iter());
+                    for (i, position) in path.positions().into_iter().enumerate() {
+                        let order = DrawOrder(first.0 + i as u32);
+                        let entity = spawn_built_item(
+                            &mut commands,
+                            &texture_atlas.0,
+                            path.object,
+                            position,
+                            order,
+                        );
+                        placed.send(ItemPlaced {
+                            entity,
+                            index: path.object,
+                            position,
+                            order,
+                        });
+                    }
+                    path.points.clear();
+                }
+                if ui.button("Clear").clicked() {
+                    path.points.clear();
+                }
+            });
+        });
+}
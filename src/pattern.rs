@@ -0,0 +1,222 @@
+use crate::{
+    catalog,
+    ghost::GhostPreview,
+    grid::{snap_position, GridSettings, Guides},
+    mouse::{spawn_built_item, DrawOrder, EditorTool, ItemPlaced},
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
+    CombinedSheet, GameState,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::f32::consts::TAU;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PatternKind {
+    Line,
+    Arc,
+    Circle,
+    Grid,
+}
+
+#[derive(Resource)]
+pub struct PatternSettings {
+    pub kind: PatternKind,
+    pub object: usize,
+    pub count: usize,
+    pub spacing: f32,
+    pub radius: f32,
+    /// Direction of a line, or where an arc or circle starts, in degrees.
+    pub angle: f32,
+    /// How much of the circle an arc covers, in degrees.
+    pub sweep: f32,
+    pub columns: usize,
+    pub rows: usize,
+    pub row_spacing: f32,
+}
+
+impl Default for PatternSettings {
+    fn default() -> Self {
+        PatternSettings {
+            kind: PatternKind::Circle,
+            object: 2,
+            count: 8,
+            spacing: 120.,
+            radius: 200.,
+            angle: 0.,
+            sweep: 180.,
+            columns: 4,
+            rows: 3,
+            row_spacing: 120.,
+        }
+    }
+}
+
+impl PatternSettings {
+    /// Positions of all copies, centred around the given point.
+    pub fn positions(&self, centre: Vec2) -> Vec<Vec2> {
+        let angle = self.angle.to_radians();
+        match self.kind {
+            PatternKind::Line => {
+                let direction = Vec2::from_angle(angle);
+                let half = (self.count.max(1) - 1) as f32 / 2.;
+                (0..self.count)
+                    .map(|i| centre + direction * (i as f32 - half) * self.spacing)
+                    .collect()
+            }
+            PatternKind::Arc => {
+                let steps = (self.count.max(2) - 1) as f32;
+                let sweep = self.sweep.to_radians();
+                (0..self.count)
+                    .map(|i| {
+                        let a = angle + sweep * i as f32 / steps;
+                        centre + Vec2::from_angle(a) * self.radius
+                    })
+                    .collect()
+            }
+            PatternKind::Circle => (0..self.count)
+                .map(|i| {
+                    let a = angle + TAU * i as f32 / self.count as f32;
+                    centre + Vec2::from_angle(a) * self.radius
+                })
+                .collect(),
+            PatternKind::Grid => {
+                let size = Vec2::new(
+                    (self.columns.max(1) - 1) as f32 * self.spacing,
+                    (self.rows.max(1) - 1) as f32 * self.row_spacing,
+                );
+                let corner = centre - size / 2.;
+                (0..self.rows)
+                    .flat_map(|row| {
+                        (0..self.columns).map(move |column| Vec2::new(column as f32, row as f32))
+                    })
+                    .map(|cell| corner + cell * Vec2::new(self.spacing, self.row_spacing))
+                    .collect()
+            }
+        }
+    }
+}
+
+pub struct PatternPlugin;
+
+impl Plugin for PatternPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatternSettings>().add_systems(
+            Update,
+            (pattern_window, place_pattern).run_if(in_state(GameState::Patterning)),
+        );
+    }
+}
+
+fn place_pattern(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    buttons: Res<Input<MouseButton>>,
+    editor_tool_q: Query<&Transform, With<EditorTool>>,
+    order_q: Query<&DrawOrder>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    settings: Res<PatternSettings>,
+    mut preview: ResMut<GhostPreview>,
+    mut placed: EventWriter<ItemPlaced>,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    if overlaps_ui(tool, &ui_q) || over_egui.0 {
+        return;
+    }
+    let centre = snap_position(&grid, &guides, tool.translation.truncate());
+    let positions = settings.positions(centre);
+    if !buttons.just_pressed(MouseButton::Left) {
+        preview.0.extend(
+            positions
+                .iter()
+                .map(|position| (settings.object, *position)),
+        );
+        return;
+    }
+    let first = DrawOrder::above(order_q.iter());
+    for (i, position) in positions.into_iter().enumerate() {
+        let order = DrawOrder(first.0 + i as u32);
+        let entity = spawn_built_item(
+            &mut commands,
+            &texture_atlas.0,
+            settings.object,
+            position,
+            order,
+        );
+        placed.send(ItemPlaced {
+            entity,
+            index: settings.object,
+            position,
+            order,
+        });
+    }
+}
+
+fn pattern_window(mut settings: ResMut<PatternSettings>, mut contexts: EguiContexts) {
+    egui::Window::new("Pattern")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for (kind, label) in [
+                    (PatternKind::Line, "Line"),
+                    (PatternKind::Arc, "Arc"),
+                    (PatternKind::Circle, "Circle"),
+                    (PatternKind::Grid, "Grid"),
+                ] {
+                    ui.selectable_value(&mut settings.kind, kind, label);
+                }
+            });
+            egui::ComboBox::from_label("Object")
+                .selected_text(catalog::name_of(settings.object))
+                .show_ui(ui, |ui| {
+                    for (i, object) in catalog::OBJECTS.iter().enumerate() {
+                        ui.selectable_value(&mut settings.object, i, object.name);
+                    }
+                });
+            let settings = &mut *settings;
+            match settings.kind {
+                PatternKind::Line => {
+                    count_field(ui, "Count:", &mut settings.count);
+                    value_field(ui, "Spacing:", &mut settings.spacing);
+                    value_field(ui, "Angle:", &mut settings.angle);
+                }
+                PatternKind::Arc => {
+                    count_field(ui, "Count:", &mut settings.count);
+                    value_field(ui, "Radius:", &mut settings.radius);
+                    value_field(ui, "Start angle:", &mut settings.angle);
+                    value_field(ui, "Sweep:", &mut settings.sweep);
+                }
+                PatternKind::Circle => {
+                    count_field(ui, "Count:", &mut settings.count);
+                    value_field(ui, "Radius:", &mut settings.radius);
+                    value_field(ui, "Start angle:", &mut settings.angle);
+                }
+                PatternKind::Grid => {
+                    count_field(ui, "Columns:", &mut settings.columns);
+                    count_field(ui, "Rows:", &mut settings.rows);
+                    value_field(ui, "Column spacing:", &mut settings.spacing);
+                    value_field(ui, "Row spacing:", &mut settings.row_spacing);
+                }
+            }
+            ui.label("Click on the canvas to place the pattern.");
+        });
+}
+
+fn count_field(ui: &mut egui::Ui, label: &str, value: &mut usize) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(value).clamp_range(1..=200));
+    });
+}
+
+fn value_field(ui: &mut egui::Ui, label: &str, value: &mut f32) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(value));
+    });
+}
@@ -0,0 +1,340 @@
+use crate::{
+    catalog::{self, ObjectRole},
+    mouse::{BuiltItem, EditorTool, ItemName},
+    GameState, GAME_HEIGHT, PLAY_COLUMN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashSet;
+
+/// Where the player sits on the game's screen, as a share of its height above the middle.
+pub const PLAYER_SCREEN_Y: f32 = 0.25;
+const PLAYER_Z: f32 = 800.;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Steering {
+    Keyboard,
+    Mouse,
+}
+
+/// How the player and the structure move in the game.
+#[derive(Resource)]
+pub struct PlaySettings {
+    /// Pixels per second the structure scrolls up.
+    pub fall_speed: f32,
+    /// Fastest the player moves sideways, in pixels per second.
+    pub player_speed: f32,
+    pub hitbox: Vec2,
+    /// Hazards passing closer than this without a hit count as near misses.
+    pub near_miss: f32,
+    pub steering: Steering,
+}
+
+impl Default for PlaySettings {
+    fn default() -> Self {
+        PlaySettings {
+            fall_speed: 300.,
+            player_speed: 500.,
+            hitbox: Vec2::new(60., 80.),
+            near_miss: 25.,
+            steering: Steering::Keyboard,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PlayEventKind {
+    Hit,
+    Pickup,
+    NearMiss,
+    Sensor,
+}
+
+impl PlayEventKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PlayEventKind::Hit => "Hit",
+            PlayEventKind::Pickup => "Picked up",
+            PlayEventKind::NearMiss => "Near miss",
+            PlayEventKind::Sensor => "Released a plane",
+        }
+    }
+}
+
+struct PlayEvent {
+    time: f32,
+    kind: PlayEventKind,
+    name: &'static str,
+}
+
+/// One run through the structure, from the first object entering the screen to the last one leaving it.
+#[derive(Resource, Default)]
+struct PlayRun {
+    /// Camera position to go back to once playing stops.
+    editor_camera: Vec2,
+    start_y: f32,
+    end_y: f32,
+    elapsed: f32,
+    finished: bool,
+    touched: HashSet<Entity>,
+    /// Hazards currently inside the near miss margin.
+    close: HashSet<Entity>,
+    log: Vec<PlayEvent>,
+}
+
+impl PlayRun {
+    fn count(&self, kind: PlayEventKind) -> usize {
+        self.log.iter().filter(|event| event.kind == kind).count()
+    }
+}
+
+#[derive(Component)]
+struct Player;
+
+pub struct PlayPlugin;
+
+impl Plugin for PlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlaySettings>()
+            .init_resource::<PlayRun>()
+            .add_systems(OnEnter(GameState::Playing), start_playing)
+            .add_systems(
+                Update,
+                (play_step, play_window)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), stop_playing);
+    }
+}
+
+fn start_playing(
+    mut commands: Commands,
+    mut run: ResMut<PlayRun>,
+    settings: Res<PlaySettings>,
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    camera_q: Query<&Transform, With<Camera>>,
+) {
+    let camera = camera_q.single().translation.truncate();
+    let (mut top, mut bottom) = (f32::MIN, f32::MAX);
+    for (trans, sprite, visibility) in item_q.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let half = catalog::size_of(sprite.index).y / 2.;
+        top = top.max(trans.translation.y + half);
+        bottom = bottom.min(trans.translation.y - half);
+    }
+    if top < bottom {
+        top = camera.y;
+        bottom = camera.y;
+    }
+    // Starts with the whole structure just below the screen and ends once it left over the top.
+    *run = PlayRun {
+        editor_camera: camera,
+        start_y: top + GAME_HEIGHT / 2.,
+        end_y: bottom - GAME_HEIGHT / 2.,
+        ..Default::default()
+    };
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1., 0.4, 0.7, 0.8),
+                custom_size: Some(settings.hitbox),
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(0., run.start_y, PLAYER_Z),
+            ..Default::default()
+        },
+        Player,
+    ));
+}
+
+fn play_step(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    settings: Res<PlaySettings>,
+    mut run: ResMut<PlayRun>,
+    mut camera_q: Query<&mut Transform, (With<Camera>, Without<Player>)>,
+    mut player_q: Query<(&mut Transform, &mut Sprite), With<Player>>,
+    editor_tool_q: Query<&Transform, (With<EditorTool>, Without<Player>, Without<Camera>)>,
+    item_q: Query<
+        (
+            Entity,
+            &Transform,
+            &TextureAtlasSprite,
+            &ItemName,
+            &Visibility,
+        ),
+        (With<BuiltItem>, Without<Player>, Without<Camera>),
+    >,
+) {
+    let Ok((mut player, mut sprite)) = player_q.get_single_mut() else {
+        return;
+    };
+    let Ok(mut camera) = camera_q.get_single_mut() else {
+        return;
+    };
+    sprite.custom_size = Some(settings.hitbox);
+    if run.finished {
+        return;
+    }
+    let delta = time.delta_seconds();
+    run.elapsed += delta;
+    let camera_y = (run.start_y - settings.fall_speed * run.elapsed).max(run.end_y);
+    if camera_y <= run.end_y {
+        run.finished = true;
+    }
+    camera.translation.x = 0.;
+    camera.translation.y = camera_y;
+
+    let step = settings.player_speed * delta;
+    let mut x = player.translation.x;
+    match settings.steering {
+        Steering::Keyboard => {
+            if keys.any_pressed([KeyCode::A, KeyCode::Left]) {
+                x -= step;
+            }
+            if keys.any_pressed([KeyCode::D, KeyCode::Right]) {
+                x += step;
+            }
+        }
+        Steering::Mouse => {
+            if let Ok(cursor) = editor_tool_q.get_single() {
+                x += (cursor.translation.x - x).clamp(-step, step);
+            }
+        }
+    }
+    let limit = (PLAY_COLUMN_WIDTH - settings.hitbox.x) / 2.;
+    player.translation.x = x.clamp(-limit, limit);
+    player.translation.y = camera_y + GAME_HEIGHT * PLAYER_SCREEN_Y;
+
+    let position = player.translation;
+    let time = run.elapsed;
+    for (entity, trans, item_sprite, name, visibility) in item_q.iter() {
+        if *visibility == Visibility::Hidden || run.touched.contains(&entity) {
+            continue;
+        }
+        let size = catalog::size_of(item_sprite.index);
+        let role = catalog::role_of(item_sprite.index);
+        if collide(position, settings.hitbox, trans.translation, size).is_some() {
+            run.touched.insert(entity);
+            run.close.remove(&entity);
+            let kind = match role {
+                ObjectRole::Hazard => PlayEventKind::Hit,
+                ObjectRole::Pickup => PlayEventKind::Pickup,
+                ObjectRole::Sensor => PlayEventKind::Sensor,
+            };
+            run.log.push(PlayEvent {
+                time,
+                kind,
+                name: name.name,
+            });
+            continue;
+        }
+        if role != ObjectRole::Hazard {
+            continue;
+        }
+        let margin = Vec2::splat(settings.near_miss * 2.);
+        let near = collide(position, settings.hitbox + margin, trans.translation, size).is_some();
+        if near {
+            run.close.insert(entity);
+        } else if run.close.remove(&entity) {
+            run.log.push(PlayEvent {
+                time,
+                kind: PlayEventKind::NearMiss,
+                name: name.name,
+            });
+        }
+    }
+}
+
+fn play_window(
+    mut settings: ResMut<PlaySettings>,
+    mut run: ResMut<PlayRun>,
+    mut player_q: Query<&mut Transform, With<Player>>,
+    keys: Res<Input<KeyCode>>,
+    mut next: ResMut<NextState<GameState>>,
+    mut contexts: EguiContexts,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next.set(GameState::Building);
+    }
+    egui::Window::new("Play")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Steer with:");
+                ui.selectable_value(&mut settings.steering, Steering::Keyboard, "A/D, arrows");
+                ui.selectable_value(&mut settings.steering, Steering::Mouse, "Mouse");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Fall speed:");
+                ui.add(egui::DragValue::new(&mut settings.fall_speed).clamp_range(10..=3000));
+                ui.label("Player speed:");
+                ui.add(egui::DragValue::new(&mut settings.player_speed).clamp_range(0..=3000));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Hitbox:");
+                ui.add(egui::DragValue::new(&mut settings.hitbox.x).clamp_range(1..=500));
+                ui.add(egui::DragValue::new(&mut settings.hitbox.y).clamp_range(1..=500));
+                ui.label("Near miss:");
+                ui.add(egui::DragValue::new(&mut settings.near_miss).clamp_range(0..=200));
+            });
+            ui.separator();
+            ui.label(format!(
+                "{:.1} s{}   hits: {}   pickups: {}   near misses: {}",
+                run.elapsed,
+                if run.finished { ", finished" } else { "" },
+                run.count(PlayEventKind::Hit),
+                run.count(PlayEventKind::Pickup),
+                run.count(PlayEventKind::NearMiss),
+            ));
+            egui::ScrollArea::vertical()
+                .max_height(120.)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for event in run.log.iter() {
+                        ui.label(format!(
+                            "{:.1} s  {} {}",
+                            event.time,
+                            event.kind.label(),
+                            event.name
+                        ));
+                    }
+                });
+            ui.horizontal(|ui| {
+                if ui.button("Restart").clicked() {
+                    *run = PlayRun {
+                        editor_camera: run.editor_camera,
+                        start_y: run.start_y,
+                        end_y: run.end_y,
+                        ..Default::default()
+                    };
+                    for mut player in player_q.iter_mut() {
+                        player.translation.x = 0.;
+                    }
+                }
+                if ui.button("Stop").on_hover_text("Escape").clicked() {
+                    next.set(GameState::Building);
+                }
+            });
+        });
+}
+
+fn stop_playing(
+    mut commands: Commands,
+    run: Res<PlayRun>,
+    player_q: Query<Entity, With<Player>>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+) {
+    for entity in player_q.iter() {
+        commands.entity(entity).despawn();
+    }
+    for mut camera in camera_q.iter_mut() {
+        camera.translation.x = run.editor_camera.x;
+        camera.translation.y = run.editor_camera.y;
+    }
+}
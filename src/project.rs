@@ -0,0 +1,503 @@
+use crate::{
+    anchor::{Anchor, AnchorMode},
+    catalog,
+    grid::{GridSettings, Guide, Guides},
+    layers::{ItemLayer, Layers},
+    mouse::{spawn_built_item, BuiltButton, BuiltItem, DrawOrder, ItemName},
+    recent::RecentFiles,
+    scoring::StructureMeta,
+    structure::StructureFile,
+    CombinedSheet, UiState,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const PROJECTS_DIR: &str = "./projects";
+pub const PROJECT_EXTENSION: &str = "ron";
+
+/// Only the ordering of z matters, it is turned back into draw order when loading.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectItem {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    #[serde(default)]
+    pub layer: u32,
+}
+
+/// Everything needed to continue editing later. Unlike the game export nothing gets lost here.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Project {
+    pub name: String,
+    pub weight: String,
+    pub notes: String,
+    pub camera: (f32, f32),
+    pub anchor_mode: AnchorMode,
+    pub custom_anchor: (f32, f32),
+    pub grid: GridSettings,
+    pub guides: Vec<Guide>,
+    pub layers: Layers,
+    pub items: Vec<ProjectItem>,
+}
+
+impl Project {
+    pub fn read(path: &Path) -> Result<Project, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&text).map_err(|e| e.to_string())
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    /// Imported objects go back to where they were exported from and the anchor to where it was, so exporting again gives the same file.
+    pub fn from_structure(name: String, structure: StructureFile, origin: Vec2) -> Project {
+        Project {
+            name,
+            weight: structure.weight,
+            anchor_mode: AnchorMode::Custom,
+            custom_anchor: (origin.x, origin.y),
+            items: structure
+                .items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| ProjectItem {
+                    name: item.name,
+                    x: item.x + origin.x,
+                    y: item.y + origin.y,
+                    z: i as f32,
+                    layer: 0,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn path_for(name: &str) -> PathBuf {
+        let name = if name.is_empty() { "export" } else { name };
+        Path::new(PROJECTS_DIR).join(format!("{}.{}", name, PROJECT_EXTENSION))
+    }
+}
+
+#[derive(Event)]
+pub struct SaveProject;
+
+#[derive(Event)]
+pub struct LoadProject {
+    pub path: PathBuf,
+    /// Recovered work was never saved, so it stays marked as unsaved after loading.
+    pub recovered: bool,
+}
+
+/// Opens either a project or a game structure, depending on the extension.
+#[derive(Event)]
+pub struct OpenFile(pub PathBuf);
+
+/// Whether there are changes that were neither exported nor saved.
+#[derive(Resource, Default)]
+pub struct Dirty {
+    pub dirty: bool,
+    settle: Option<bool>,
+}
+
+impl Dirty {
+    pub fn mark(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Objects spawned by a load only show up as changes a frame later, this decides how they count.
+    fn settle(&mut self, dirty: bool) {
+        self.settle = Some(dirty);
+    }
+}
+
+/// File waiting for the user to decide what happens to the unsaved changes.
+#[derive(Resource, Default)]
+struct PendingOpen {
+    path: Option<PathBuf>,
+    /// Saved or discarded, it can be opened once the save went through.
+    confirmed: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct ProjectFiles {
+    pub list: Vec<PathBuf>,
+    pub status: String,
+}
+
+impl ProjectFiles {
+    fn refresh(&mut self) {
+        self.list = std::fs::read_dir(PROJECTS_DIR)
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|e| e == PROJECT_EXTENSION))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.list.sort();
+    }
+}
+
+/// Read access to the whole editing session.
+#[derive(SystemParam)]
+pub struct Session<'w, 's> {
+    items: Query<
+        'w,
+        's,
+        (
+            &'static Transform,
+            &'static ItemName,
+            Option<&'static ItemLayer>,
+        ),
+        With<BuiltItem>,
+    >,
+    camera: Query<'w, 's, &'static Transform, With<Camera>>,
+    ui_state: Res<'w, UiState>,
+    anchor: Res<'w, Anchor>,
+    grid: Res<'w, GridSettings>,
+    guides: Res<'w, Guides>,
+    layers: Res<'w, Layers>,
+}
+
+impl<'w, 's> Session<'w, 's> {
+    pub fn capture(&self) -> Project {
+        let camera = self.camera.single().translation;
+        Project {
+            name: self.ui_state.name.clone(),
+            weight: self.ui_state.weight_s.clone(),
+            notes: self.ui_state.notes.clone(),
+            camera: (camera.x, camera.y),
+            anchor_mode: self.anchor.mode,
+            custom_anchor: (self.anchor.custom.x, self.anchor.custom.y),
+            grid: self.grid.clone(),
+            guides: self.guides.0.clone(),
+            layers: self.layers.clone(),
+            items: self
+                .items
+                .iter()
+                .map(|(trans, name, layer)| ProjectItem {
+                    name: name.name.to_string(),
+                    x: trans.translation.x,
+                    y: trans.translation.y,
+                    z: trans.translation.z,
+                    // Objects placed this frame only get their layer on the next one.
+                    layer: layer.map_or(self.layers.active, |layer| layer.0),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Write access to the whole editing session, used to replace it with a loaded one.
+#[derive(SystemParam)]
+pub struct SessionMut<'w, 's> {
+    commands: Commands<'w, 's>,
+    texture_atlas: Res<'w, CombinedSheet>,
+    items: Query<'w, 's, Entity, With<BuiltItem>>,
+    buttons: Query<'w, 's, Entity, With<BuiltButton>>,
+    camera: Query<'w, 's, &'static mut Transform, With<Camera>>,
+    ui_state: ResMut<'w, UiState>,
+    anchor: ResMut<'w, Anchor>,
+    grid: ResMut<'w, GridSettings>,
+    guides: ResMut<'w, Guides>,
+    layers: ResMut<'w, Layers>,
+}
+
+impl<'w, 's> SessionMut<'w, 's> {
+    pub fn clear(&mut self) {
+        for entity in self.items.iter().chain(self.buttons.iter()) {
+            self.commands.entity(entity).despawn();
+        }
+    }
+
+    pub fn restore(&mut self, mut project: Project) {
+        self.clear();
+        project.items.sort_by(|a, b| a.z.total_cmp(&b.z));
+        for (order, item) in project.items.iter().enumerate() {
+            let Some(index) = catalog::index_of(&item.name) else {
+                continue;
+            };
+            let entity = spawn_built_item(
+                &mut self.commands,
+                &self.texture_atlas.0,
+                index,
+                Vec2::new(item.x, item.y),
+                DrawOrder(order as u32),
+            );
+            self.commands.entity(entity).insert(ItemLayer(item.layer));
+        }
+        for mut trans in self.camera.iter_mut() {
+            trans.translation.x = project.camera.0;
+            trans.translation.y = project.camera.1;
+        }
+        self.ui_state.name = project.name;
+        self.ui_state.weight_s = project.weight;
+        self.ui_state.notes = project.notes;
+        self.anchor.mode = project.anchor_mode;
+        self.anchor.custom = Vec2::new(project.custom_anchor.0, project.custom_anchor.1);
+        *self.grid = project.grid;
+        self.guides.0 = project.guides;
+        *self.layers = project.layers;
+    }
+}
+
+pub struct ProjectPlugin;
+
+impl Plugin for ProjectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveProject>()
+            .add_event::<LoadProject>()
+            .add_event::<OpenFile>()
+            .init_resource::<ProjectFiles>()
+            .init_resource::<Dirty>()
+            .init_resource::<PendingOpen>()
+            .add_systems(Startup, |mut files: ResMut<ProjectFiles>| files.refresh())
+            .add_systems(
+                Update,
+                (
+                    project_window,
+                    open_confirmation,
+                    save_project,
+                    open_file,
+                    load_project,
+                )
+                    .chain(),
+            )
+            .add_systems(PostUpdate, (track_changes, update_title).chain());
+    }
+}
+
+fn save_project(
+    mut events: EventReader<SaveProject>,
+    session: Session,
+    mut files: ResMut<ProjectFiles>,
+    mut dirty: ResMut<Dirty>,
+    mut recent: ResMut<RecentFiles>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    let project = session.capture();
+    let path = Project::path_for(&project.name);
+    files.status = match project.write(&path) {
+        Ok(()) => {
+            dirty.clear();
+            recent.add(&path);
+            format!("Saved {}", path.display())
+        }
+        Err(e) => format!("Saving failed: {}", e),
+    };
+    files.refresh();
+}
+
+fn load_project(
+    mut events: EventReader<LoadProject>,
+    mut session: SessionMut,
+    mut files: ResMut<ProjectFiles>,
+    mut dirty: ResMut<Dirty>,
+    mut recent: ResMut<RecentFiles>,
+) {
+    let Some(LoadProject { path, recovered }) = events.iter().last() else {
+        return;
+    };
+    files.status = match Project::read(path) {
+        Ok(project) => {
+            session.restore(project);
+            dirty.settle(*recovered);
+            if !recovered {
+                recent.add(path);
+            }
+            format!("Loaded {}", path.display())
+        }
+        Err(e) => format!("Loading failed: {}", e),
+    };
+}
+
+fn open_file(
+    mut events: EventReader<OpenFile>,
+    mut load: EventWriter<LoadProject>,
+    mut session: SessionMut,
+    mut files: ResMut<ProjectFiles>,
+    mut dirty: ResMut<Dirty>,
+    mut recent: ResMut<RecentFiles>,
+    mut pending: ResMut<PendingOpen>,
+) {
+    let path = if let Some(OpenFile(path)) = events.iter().last() {
+        if dirty.dirty {
+            *pending = PendingOpen {
+                path: Some(path.clone()),
+                confirmed: false,
+            };
+            return;
+        }
+        path.clone()
+    } else if pending.confirmed {
+        let path = pending.path.take();
+        pending.confirmed = false;
+        // Saving failed, the reason is in the status line and nothing gets replaced.
+        match path {
+            Some(path) if !dirty.dirty => path,
+            _ => return,
+        }
+    } else {
+        return;
+    };
+    let path = &path;
+    if path.extension().is_some_and(|e| e == PROJECT_EXTENSION) {
+        load.send(LoadProject {
+            path: path.clone(),
+            recovered: false,
+        });
+        return;
+    }
+    files.status = match StructureFile::read(path) {
+        Ok(structure) => {
+            let name = path
+                .file_name()
+                .map_or(String::new(), |n| n.to_string_lossy().replace('.', ""));
+            // Without a meta file the anchor is unknown, the coordinates are taken as they are.
+            let origin = StructureMeta::read_for(path)
+                .ok()
+                .and_then(|meta| meta.origin())
+                .unwrap_or(Vec2::ZERO);
+            session.restore(Project::from_structure(name, structure, origin));
+            dirty.settle(false);
+            recent.add(path);
+            format!("Imported {}", path.display())
+        }
+        Err(e) => format!("Importing {} failed: {}", path.display(), e),
+    };
+}
+
+fn open_confirmation(
+    mut pending: ResMut<PendingOpen>,
+    mut save: EventWriter<SaveProject>,
+    mut dirty: ResMut<Dirty>,
+    mut contexts: EguiContexts,
+) {
+    if pending.confirmed {
+        return;
+    }
+    let Some(path) = pending.path.clone() else {
+        return;
+    };
+    egui::Window::new("Unsaved changes")
+        .id(egui::Id::new("open confirmation"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., 0.))
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!(
+                "The structure has unsaved changes, opening {} replaces them.",
+                path.file_name()
+                    .map_or(String::new(), |n| n.to_string_lossy().to_string())
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    save.send(SaveProject);
+                    pending.confirmed = true;
+                }
+                if ui.button("Discard").clicked() {
+                    dirty.clear();
+                    pending.confirmed = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    pending.path = None;
+                }
+            });
+        });
+}
+
+fn project_window(
+    mut ui_state: ResMut<UiState>,
+    mut files: ResMut<ProjectFiles>,
+    mut save: EventWriter<SaveProject>,
+    mut open: EventWriter<OpenFile>,
+    mut dirty: ResMut<Dirty>,
+    mut contexts: EguiContexts,
+) {
+    egui::Window::new("Project")
+        .default_pos(egui::pos2(15., 165.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Notes:");
+            if ui.text_edit_multiline(&mut ui_state.notes).changed() {
+                dirty.mark();
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save project").clicked() {
+                    save.send(SaveProject);
+                }
+                if ui.button("Refresh").clicked() {
+                    files.refresh();
+                }
+            });
+            ui.separator();
+            for path in files.list.iter() {
+                let label = path
+                    .file_stem()
+                    .map_or(String::new(), |s| s.to_string_lossy().to_string());
+                if ui.button(format!("Load {}", label)).clicked() {
+                    open.send(OpenFile(path.clone()));
+                }
+            }
+            if !files.status.is_empty() {
+                ui.separator();
+                ui.label(&files.status);
+            }
+        });
+}
+
+fn track_changes(
+    added: Query<(), Added<BuiltItem>>,
+    changed: Query<
+        (),
+        (
+            With<BuiltItem>,
+            Or<(Changed<Transform>, Changed<TextureAtlasSprite>)>,
+        ),
+    >,
+    mut removed: RemovedComponents<BuiltItem>,
+    mut dirty: ResMut<Dirty>,
+) {
+    let any_change = !added.is_empty() || !changed.is_empty() || removed.iter().count() > 0;
+    if let Some(settled) = dirty.settle.take() {
+        dirty.dirty = settled;
+    } else if any_change {
+        dirty.mark();
+    }
+}
+
+fn update_title(
+    dirty: Res<Dirty>,
+    ui_state: Res<UiState>,
+    mut windows_q: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let name = if ui_state.name.is_empty() {
+        "export"
+    } else {
+        &ui_state.name
+    };
+    let title = format!("pupik - {}{}", name, if dirty.dirty { "*" } else { "" });
+    for mut window in windows_q.iter_mut() {
+        if window.title != title {
+            window.title = title.clone();
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::project::OpenFile;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const MAX_RECENT: usize = 10;
+
+/// Recently imported, exported or saved files, kept in the user's config folder.
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct RecentFiles {
+    pub paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("pupik_editor").join("recent.ron"))
+    }
+
+    fn load() -> RecentFiles {
+        Self::config_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| ron::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::config_path() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(text) = ron::to_string(self) {
+            let _ = std::fs::write(path, text);
+        }
+    }
+
+    pub fn add(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.paths.retain(|p| p != &path);
+        self.paths.insert(0, path);
+        self.paths.truncate(MAX_RECENT);
+        self.save();
+    }
+}
+
+/// File given on the command line, opened as soon as the editor is ready.
+#[derive(Resource)]
+pub struct StartupFile(pub Option<PathBuf>);
+
+#[derive(Resource)]
+struct StartPanel(bool);
+
+pub struct RecentPlugin;
+
+impl Plugin for RecentPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RecentFiles::load())
+            .add_systems(Startup, open_startup_file)
+            .add_systems(Update, (start_panel, file_drop));
+    }
+}
+
+fn open_startup_file(
+    mut commands: Commands,
+    startup_file: Res<StartupFile>,
+    recent: Res<RecentFiles>,
+    mut open: EventWriter<OpenFile>,
+) {
+    if let Some(path) = startup_file.0.as_ref() {
+        open.send(OpenFile(path.clone()));
+    }
+    commands.insert_resource(StartPanel(
+        startup_file.0.is_none() && !recent.paths.is_empty(),
+    ));
+}
+
+fn start_panel(
+    mut panel: ResMut<StartPanel>,
+    recent: Res<RecentFiles>,
+    mut open: EventWriter<OpenFile>,
+    mut contexts: EguiContexts,
+) {
+    if !panel.0 {
+        return;
+    }
+    egui::Window::new("Recent files")
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0., 60.))
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for path in recent.paths.iter() {
+                let label = path
+                    .file_name()
+                    .map_or(String::new(), |s| s.to_string_lossy().to_string());
+                if ui
+                    .button(label)
+                    .on_hover_text(path.display().to_string())
+                    .clicked()
+                {
+                    open.send(OpenFile(path.clone()));
+                    panel.0 = false;
+                }
+            }
+            ui.separator();
+            if ui.button("New structure").clicked() {
+                panel.0 = false;
+            }
+        });
+}
+
+fn file_drop(mut drops: EventReader<FileDragAndDrop>, mut open: EventWriter<OpenFile>) {
+    for drop in drops.iter() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = drop {
+            open.send(OpenFile(path_buf.clone()));
+        }
+    }
+}
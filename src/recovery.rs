@@ -0,0 +1,103 @@
+use crate::project::{LoadProject, Session};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::path::Path;
+
+const RECOVERY_FILE: &str = "./recovery.ron";
+/// Exists for as long as the editor runs, so finding it on launch means the last session crashed.
+const SESSION_LOCK: &str = "./.session.lock";
+const AUTOSAVE_SECONDS: f32 = 30.;
+
+#[derive(Resource)]
+struct Autosave {
+    timer: Timer,
+    last: String,
+}
+
+#[derive(Resource, Default)]
+struct RecoveryOffer(bool);
+
+pub struct RecoveryPlugin;
+
+impl Plugin for RecoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Autosave {
+            timer: Timer::from_seconds(AUTOSAVE_SECONDS, TimerMode::Repeating),
+            last: String::new(),
+        })
+        .init_resource::<RecoveryOffer>()
+        .add_systems(Startup, check_previous_session)
+        .add_systems(Update, (autosave, recovery_dialog))
+        .add_systems(Last, clean_exit);
+    }
+}
+
+fn check_previous_session(mut offer: ResMut<RecoveryOffer>) {
+    offer.0 = Path::new(SESSION_LOCK).exists() && Path::new(RECOVERY_FILE).exists();
+    if !offer.0 {
+        let _ = std::fs::remove_file(RECOVERY_FILE);
+    }
+    let _ = std::fs::write(SESSION_LOCK, "");
+}
+
+fn autosave(
+    time: Res<Time>,
+    mut autosave: ResMut<Autosave>,
+    offer: Res<RecoveryOffer>,
+    session: Session,
+) {
+    // Overwriting the snapshot before the user decides would lose the very thing we offer to restore.
+    if offer.0 || !autosave.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let project = session.capture();
+    let Ok(text) = ron::to_string(&project) else {
+        return;
+    };
+    if text == autosave.last {
+        return;
+    }
+    if project.write(Path::new(RECOVERY_FILE)).is_ok() {
+        autosave.last = text;
+    }
+}
+
+fn recovery_dialog(
+    mut offer: ResMut<RecoveryOffer>,
+    mut load: EventWriter<LoadProject>,
+    mut contexts: EguiContexts,
+) {
+    if !offer.0 {
+        return;
+    }
+    egui::Window::new("Restore previous session?")
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., 0.))
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("The editor was not closed properly last time.");
+            ui.label("Do you want to restore the automatically saved work?");
+            ui.horizontal(|ui| {
+                if ui.button("Restore").clicked() {
+                    load.send(LoadProject {
+                        path: RECOVERY_FILE.into(),
+                        recovered: true,
+                    });
+                    offer.0 = false;
+                }
+                if ui.button("Discard").clicked() {
+                    let _ = std::fs::remove_file(RECOVERY_FILE);
+                    offer.0 = false;
+                }
+            });
+        });
+}
+
+fn clean_exit(mut exit: EventReader<AppExit>) {
+    if exit.iter().count() == 0 {
+        return;
+    }
+    let _ = std::fs::remove_file(RECOVERY_FILE);
+    let _ = std::fs::remove_file(SESSION_LOCK);
+}
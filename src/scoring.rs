@@ -0,0 +1,224 @@
+use crate::{
+    cache::{Cached, StructureVersion},
+    catalog::{self, ObjectRole},
+    layers::Layers,
+    mouse::BuiltItem,
+    passability::{self, Player},
+    play::PlaySettings,
+    GAME_HEIGHT, PLAY_COLUMN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Next to the structures folder, so the game never tries to spawn these.
+pub const META_DIR: &str = "./structures_meta";
+
+/// How hard a structure is and how much it gives back. Both are only meant to compare structures with each other.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Scores {
+    pub difficulty: f32,
+    pub reward: f32,
+    pub hazards_per_screen: f32,
+    /// Narrowest gap the player has to get through, None without damaging objects.
+    pub narrowest_gap: Option<f32>,
+    /// Share of the player's sideways speed needed to get through, above 1 is impossible.
+    pub lateral: f32,
+    pub planes: usize,
+    pub pull: f32,
+    pub passable: bool,
+}
+
+/// Scores saved next to an exported structure.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct StructureMeta {
+    pub weight: String,
+    /// The anchor the exported coordinates are relative to, in the editor where the play column is centred at x 0.
+    /// Missing for structures exported before it was saved.
+    pub origin: Option<(f32, f32)>,
+    pub scores: Scores,
+}
+
+impl StructureMeta {
+    pub fn path_for(name: &str) -> PathBuf {
+        Path::new(META_DIR).join(format!("{}.ron", name))
+    }
+
+    /// The file saved with an exported structure, exports go to `structures/<name>` and `structures_meta/<name>.ron` side by side.
+    pub fn read_for(structure: &Path) -> Result<StructureMeta, String> {
+        let name = structure
+            .file_name()
+            .ok_or_else(|| "not a file".to_string())?
+            .to_string_lossy();
+        let folder = structure
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or_else(|| Path::new("."));
+        let meta_folder = Path::new(META_DIR).file_name().unwrap_or_default();
+        let path = folder.join(meta_folder).join(format!("{}.ron", name));
+        let text =
+            std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        ron::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn origin(&self) -> Option<Vec2> {
+        self.origin.map(|(x, y)| Vec2::new(x, y))
+    }
+
+    pub fn write(&self, name: &str) -> Result<(), String> {
+        std::fs::create_dir_all(META_DIR).map_err(|e| e.to_string())?;
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(Self::path_for(name), text).map_err(|e| e.to_string())
+    }
+}
+
+pub fn score(items: &[(usize, Vec2)], player: Player) -> Scores {
+    let analysis = passability::analyse(items, player);
+    let hazards: Vec<&(usize, Vec2)> = items
+        .iter()
+        .filter(|(index, _)| catalog::role_of(*index) == ObjectRole::Hazard)
+        .collect();
+    let top = hazards.iter().map(|(_, p)| p.y).reduce(f32::max);
+    let bottom = hazards.iter().map(|(_, p)| p.y).reduce(f32::min);
+    let height = match (top, bottom) {
+        (Some(top), Some(bottom)) => top - bottom + player.hitbox.y,
+        _ => 0.,
+    };
+
+    // Widest way through at each height, the narrowest of those is what the player has to squeeze through.
+    let narrowest_gap = analysis
+        .reachable
+        .iter()
+        .map(|(_, ranges)| ranges.iter().map(|(from, to)| to - from).fold(0., f32::max))
+        .reduce(f32::min)
+        .map(|width| (width + player.hitbox.x).min(PLAY_COLUMN_WIDTH));
+
+    // Follows the reachable area as lazily as possible, moving sideways only when it has to.
+    let mut x: f32 = 0.;
+    let mut moved = 0.;
+    for (_, ranges) in analysis.reachable.iter() {
+        let nearest = ranges
+            .iter()
+            .map(|(from, to)| x.clamp(*from, *to))
+            .min_by(|a, b| (a - x).abs().total_cmp(&(b - x).abs()));
+        if let Some(nearest) = nearest {
+            moved += (nearest - x).abs();
+            x = nearest;
+        }
+    }
+    let available = player.speed / player.fall_speed.max(1.) * height;
+    let lateral = if available > 0. {
+        moved / available
+    } else {
+        0.
+    };
+
+    let planes = items
+        .iter()
+        .filter(|(index, _)| catalog::role_of(*index) == ObjectRole::Sensor)
+        .count();
+    // Only the part of the pull that reaches into the play column matters.
+    let pull: f32 = items
+        .iter()
+        .filter_map(|(index, position)| {
+            let gravity = catalog::gravity_of(*index)?;
+            let outside = (position.x.abs() - PLAY_COLUMN_WIDTH / 2.).max(0.);
+            Some(gravity.pull_at(outside))
+        })
+        .sum();
+    let reward = items
+        .iter()
+        .map(|(index, _)| catalog::reward_of(*index))
+        .sum();
+    let hazards_per_screen = if height > 0. {
+        hazards.len() as f32 * GAME_HEIGHT / height.max(GAME_HEIGHT / 4.)
+    } else {
+        0.
+    };
+
+    let gap_term = match narrowest_gap {
+        _ if !analysis.passable => 10.,
+        Some(gap) => (2. * player.hitbox.x / gap).min(5.),
+        None => 0.,
+    };
+    let difficulty =
+        hazards_per_screen * 0.5 + gap_term + lateral.min(2.) * 5. + planes as f32 + pull * 2.;
+
+    Scores {
+        difficulty,
+        reward,
+        hazards_per_screen,
+        narrowest_gap,
+        lateral,
+        planes,
+        pull,
+        passable: analysis.passable,
+    }
+}
+
+/// Items that end up in the exported file, as sprite indices and positions.
+pub fn exported_items(
+    item_q: &Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    layers: &Layers,
+) -> Vec<(usize, Vec2)> {
+    item_q
+        .iter()
+        .filter(|(_, _, visibility)| layers.export_hidden || **visibility != Visibility::Hidden)
+        .map(|(trans, sprite, _)| (sprite.index, trans.translation.truncate()))
+        .collect()
+}
+
+#[derive(Resource, Default)]
+struct ScoringCache(Cached<(u64, Player), Scores>);
+
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScoringCache>()
+            .add_systems(Update, scoring_window);
+    }
+}
+
+fn scoring_window(
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    layers: Res<Layers>,
+    settings: Res<PlaySettings>,
+    version: Res<StructureVersion>,
+    mut cache: ResMut<ScoringCache>,
+    mut contexts: EguiContexts,
+) {
+    let player = Player::from(&*settings);
+    egui::Window::new("Difficulty")
+        .default_pos(egui::pos2(660., 60.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let scores = cache.0.get((version.0, player), || {
+                score(&exported_items(&item_q, &layers), player)
+            });
+            ui.heading(format!(
+                "Difficulty {:.1}   Reward {:.1}",
+                scores.difficulty, scores.reward
+            ));
+            ui.label(format!(
+                "Damaging objects per screen: {:.1}",
+                scores.hazards_per_screen
+            ));
+            match (scores.passable, scores.narrowest_gap) {
+                (false, _) => ui.label("Narrowest gap: none, the player can't get through"),
+                (true, Some(gap)) => ui.label(format!("Narrowest gap: {:.0} px", gap)),
+                (true, None) => ui.label("Narrowest gap: no damaging objects"),
+            };
+            ui.label(format!(
+                "Sideways movement needed: {:.0}% of the player's speed",
+                scores.lateral * 100.
+            ));
+            ui.label(format!("Plane sensors: {}", scores.planes));
+            ui.label(format!("Gravity pull: {:.1}", scores.pull));
+        });
+}
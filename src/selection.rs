@@ -0,0 +1,316 @@
+use crate::{
+    catalog,
+    grid::{snap_position, GridSettings, Guides},
+    layers::Locked,
+    mouse::{change_item_type, topmost_item, BuiltItem, DrawOrder, EditorTool, ItemName, Selected},
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
+    transform::TransformGizmo,
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+#[derive(Default)]
+enum SelectDrag {
+    #[default]
+    None,
+    /// Moving the selection, remembering where the grabbed object started.
+    Move {
+        grabbed: Entity,
+        start: Vec2,
+        cursor: Vec2,
+    },
+    Rectangle {
+        start: Vec2,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub enum OrderCommand {
+    ToFront,
+    Forward,
+    Backward,
+    ToBack,
+}
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReorderSelection>()
+            .add_systems(
+                Update,
+                (
+                    select_and_move,
+                    selection_keys,
+                    selection_window,
+                    draw_selection,
+                )
+                    .run_if(in_state(GameState::Selecting)),
+            )
+            .add_systems(Update, reorder_selection)
+            .add_systems(OnExit(GameState::Selecting), clear_selection);
+    }
+}
+
+#[derive(Event)]
+pub struct ReorderSelection(pub OrderCommand);
+
+pub fn selection_outline(trans: &Transform, index: usize) -> (Vec2, Vec2) {
+    (
+        trans.translation.truncate(),
+        catalog::size_of(index) * trans.scale.truncate().abs() + Vec2::splat(6.),
+    )
+}
+
+fn select_and_move(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    editor_tool_q: Query<&Transform, (With<EditorTool>, Without<BuiltItem>)>,
+    mut item_q: Query<
+        (
+            Entity,
+            &mut Transform,
+            &TextureAtlasSprite,
+            Option<&Selected>,
+        ),
+        (With<BuiltItem>, Without<Locked>),
+    >,
+    ui_q: Query<(&Transform, &UISprite), (With<UISprite>, Without<BuiltItem>)>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    gizmo: Res<TransformGizmo>,
+    mut drag: Local<SelectDrag>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    let cursor = tool.translation.truncate();
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if buttons.just_pressed(MouseButton::Left) {
+        if overlaps_ui(tool, &ui_q) || over_egui.0 || gizmo.grabs(cursor) {
+            return;
+        }
+        let hit = topmost_item(
+            cursor,
+            item_q
+                .iter()
+                .map(|(e, trans, sprite, _)| (e, trans, sprite)),
+        );
+        let Some(hit) = hit else {
+            if !shift {
+                for (entity, _, _, selected) in item_q.iter() {
+                    if selected.is_some() {
+                        commands.entity(entity).remove::<Selected>();
+                    }
+                }
+            }
+            *drag = SelectDrag::Rectangle { start: cursor };
+            return;
+        };
+        let hit_selected = item_q.get(hit).is_ok_and(|(_, _, _, s)| s.is_some());
+        if shift && hit_selected {
+            commands.entity(hit).remove::<Selected>();
+            return;
+        }
+        if !shift && !hit_selected {
+            for (entity, _, _, selected) in item_q.iter() {
+                if selected.is_some() {
+                    commands.entity(entity).remove::<Selected>();
+                }
+            }
+        }
+        commands.entity(hit).insert(Selected);
+        let start = item_q
+            .get(hit)
+            .map_or(cursor, |(_, trans, _, _)| trans.translation.truncate());
+        *drag = SelectDrag::Move {
+            grabbed: hit,
+            start,
+            cursor,
+        };
+        return;
+    }
+
+    match *drag {
+        SelectDrag::None => {}
+        SelectDrag::Move {
+            grabbed,
+            start,
+            cursor: grab_cursor,
+        } => {
+            if !buttons.pressed(MouseButton::Left) {
+                *drag = SelectDrag::None;
+                return;
+            }
+            let Ok((_, grabbed_trans, _, _)) = item_q.get(grabbed) else {
+                *drag = SelectDrag::None;
+                return;
+            };
+            let target = snap_position(&grid, &guides, start + cursor - grab_cursor);
+            let delta = target - grabbed_trans.translation.truncate();
+            if delta == Vec2::ZERO {
+                return;
+            }
+            // The grabbed object may have been selected this very frame, so it is moved explicitly.
+            for (entity, mut trans, _, selected) in item_q.iter_mut() {
+                if selected.is_some() || entity == grabbed {
+                    trans.translation += delta.extend(0.);
+                }
+            }
+        }
+        SelectDrag::Rectangle { start } => {
+            let rect = Rect::from_corners(start, cursor);
+            if buttons.pressed(MouseButton::Left) {
+                gizmos.rect_2d(rect.center(), 0., rect.size(), Color::BLUE);
+                return;
+            }
+            for (entity, trans, _, _) in item_q.iter() {
+                if rect.contains(trans.translation.truncate()) {
+                    commands.entity(entity).insert(Selected);
+                }
+            }
+            *drag = SelectDrag::None;
+        }
+    }
+}
+
+fn selection_keys(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    selected_q: Query<Entity, (With<Selected>, With<BuiltItem>)>,
+    mut reorder: EventWriter<ReorderSelection>,
+    over_egui: Res<PointerOverEgui>,
+) {
+    if over_egui.0 {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Delete) {
+        for entity in selected_q.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        for entity in selected_q.iter() {
+            commands.entity(entity).remove::<Selected>();
+        }
+    }
+    for (key, command) in [
+        (KeyCode::Home, OrderCommand::ToFront),
+        (KeyCode::PageUp, OrderCommand::Forward),
+        (KeyCode::PageDown, OrderCommand::Backward),
+        (KeyCode::End, OrderCommand::ToBack),
+    ] {
+        if keys.just_pressed(key) {
+            reorder.send(ReorderSelection(command));
+        }
+    }
+}
+
+/// Reorders the whole stack and numbers it again from zero, so orders stay dense.
+fn reorder_selection(
+    mut events: EventReader<ReorderSelection>,
+    mut item_q: Query<(Entity, &mut DrawOrder, Option<&Selected>), With<BuiltItem>>,
+) {
+    for ReorderSelection(command) in events.iter() {
+        let mut stack: Vec<(Entity, DrawOrder, bool)> = item_q
+            .iter()
+            .map(|(entity, order, selected)| (entity, *order, selected.is_some()))
+            .collect();
+        stack.sort_by_key(|(_, order, _)| *order);
+        match command {
+            OrderCommand::ToFront => stack.sort_by_key(|(_, _, selected)| *selected),
+            OrderCommand::ToBack => stack.sort_by_key(|(_, _, selected)| !*selected),
+            OrderCommand::Forward => {
+                for i in (0..stack.len().saturating_sub(1)).rev() {
+                    if stack[i].2 && !stack[i + 1].2 {
+                        stack.swap(i, i + 1);
+                    }
+                }
+            }
+            OrderCommand::Backward => {
+                for i in 1..stack.len() {
+                    if stack[i].2 && !stack[i - 1].2 {
+                        stack.swap(i, i - 1);
+                    }
+                }
+            }
+        }
+        for (i, (entity, _, _)) in stack.iter().enumerate() {
+            if let Ok((_, mut order, _)) = item_q.get_mut(*entity) {
+                if order.0 != i as u32 {
+                    order.0 = i as u32;
+                }
+            }
+        }
+    }
+}
+
+fn draw_selection(
+    selected_q: Query<(&Transform, &TextureAtlasSprite), (With<Selected>, With<BuiltItem>)>,
+    mut gizmos: Gizmos,
+) {
+    for (trans, sprite) in selected_q.iter() {
+        let (centre, size) = selection_outline(trans, sprite.index);
+        gizmos.rect_2d(centre, 0., size, Color::rgb(0.1, 0.4, 1.));
+    }
+}
+
+fn selection_window(
+    mut commands: Commands,
+    mut selected_q: Query<
+        (Entity, &mut TextureAtlasSprite, &mut ItemName),
+        (With<Selected>, With<BuiltItem>),
+    >,
+    mut reorder: EventWriter<ReorderSelection>,
+    mut replace_with: Local<usize>,
+    mut contexts: EguiContexts,
+) {
+    egui::Window::new("Selection")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("{} selected", selected_q.iter().count()));
+            ui.horizontal(|ui| {
+                for (label, command) in [
+                    ("To front", OrderCommand::ToFront),
+                    ("Forward", OrderCommand::Forward),
+                    ("Backward", OrderCommand::Backward),
+                    ("To back", OrderCommand::ToBack),
+                ] {
+                    if ui.button(label).clicked() {
+                        reorder.send(ReorderSelection(command));
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("replace_type")
+                    .selected_text(catalog::name_of(*replace_with))
+                    .show_ui(ui, |ui| {
+                        for (i, object) in catalog::OBJECTS.iter().enumerate() {
+                            ui.selectable_value(&mut *replace_with, i, object.name);
+                        }
+                    });
+                if ui.button("Change type").clicked() {
+                    for (_, mut sprite, mut name) in selected_q.iter_mut() {
+                        change_item_type(&mut sprite, &mut name, *replace_with);
+                    }
+                }
+            });
+            if ui.button("Delete").clicked() {
+                for (entity, _, _) in selected_q.iter() {
+                    commands.entity(entity).despawn();
+                }
+            }
+        });
+}
+
+fn clear_selection(mut commands: Commands, selected_q: Query<Entity, With<Selected>>) {
+    for entity in selected_q.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+}
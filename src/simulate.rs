@@ -0,0 +1,430 @@
+use crate::{
+    anchor::Anchor,
+    catalog::{self, OBJECTS},
+    ghost::GhostPreview,
+    layers::Layers,
+    mouse::BuiltItem,
+    play::PlaySettings,
+    scoring::exported_items,
+    structure::StructureFile,
+    GameState, UiState, PLAY_COLUMN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use rand::{distributions::WeightedIndex, prelude::*};
+use std::path::PathBuf;
+
+pub const STRUCTURES_DIR: &str = "./structures";
+
+/// Something the game picks from when spawning: a structure, or one of the objects on its own.
+#[derive(Clone)]
+pub struct SpawnSource {
+    pub name: String,
+    pub weight: f64,
+    pub items: Vec<(usize, Vec2)>,
+    /// Single objects are spawned at a random x, structures where they were exported.
+    pub single: bool,
+}
+
+/// Every structure in the structures folder, with the files that couldn't be read.
+pub fn read_structures() -> (Vec<SpawnSource>, Vec<String>) {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(STRUCTURES_DIR)
+        .map(|dir| {
+            dir.filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    let mut sources = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match StructureFile::read(&path) {
+            Ok(file) => sources.push(SpawnSource {
+                name,
+                weight: file.weight.parse().unwrap_or_default(),
+                items: file
+                    .items
+                    .iter()
+                    .filter_map(|item| {
+                        Some((catalog::index_of(&item.name)?, Vec2::new(item.x, item.y)))
+                    })
+                    .collect(),
+                single: false,
+            }),
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+    (sources, errors)
+}
+
+/// One pick of the random selection, laid out where it appears in the stream.
+pub struct Spawn {
+    pub source: usize,
+    pub top: f32,
+    pub bottom: f32,
+    pub items: Vec<(usize, Vec2)>,
+}
+
+/// Picks `count` sources the way the game does and stacks them from y 0 downwards, `gap` pixels apart.
+pub fn simulate(
+    sources: &[SpawnSource],
+    count: usize,
+    seed: u64,
+    gap: f32,
+) -> Result<Vec<Spawn>, String> {
+    let weights = WeightedIndex::new(sources.iter().map(|source| source.weight.max(0.)))
+        .map_err(|_| "Nothing has a weight above 0".to_string())?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut spawns = Vec::with_capacity(count);
+    let mut y = 0.;
+    for _ in 0..count {
+        let index = weights.sample(&mut rng);
+        let source = &sources[index];
+        let top = source
+            .items
+            .iter()
+            .map(|(i, p)| p.y + catalog::size_of(*i).y / 2.)
+            .fold(f32::MIN, f32::max);
+        let bottom = source
+            .items
+            .iter()
+            .map(|(i, p)| p.y - catalog::size_of(*i).y / 2.)
+            .fold(f32::MAX, f32::min);
+        if top < bottom {
+            continue;
+        }
+        let x = match source.items.first() {
+            Some((i, _)) if source.single => {
+                let limit = ((PLAY_COLUMN_WIDTH - catalog::size_of(*i).x) / 2.).max(0.);
+                rng.gen_range(-limit..=limit)
+            }
+            _ => 0.,
+        };
+        let offset = Vec2::new(x, y - top);
+        spawns.push(Spawn {
+            source: index,
+            top: y,
+            bottom: y - (top - bottom),
+            items: source
+                .items
+                .iter()
+                .map(|(i, p)| (*i, *p + offset))
+                .collect(),
+        });
+        y -= top - bottom + gap;
+    }
+    Ok(spawns)
+}
+
+#[derive(Resource)]
+struct Simulation {
+    seed: u64,
+    count: usize,
+    gap: f32,
+    /// Weights of the single objects, by sprite index.
+    single_weights: Vec<f64>,
+    include_current: bool,
+    sources: Vec<SpawnSource>,
+    errors: Vec<String>,
+    spawns: Vec<Spawn>,
+    /// Where the first spawn starts, below everything on the canvas.
+    origin: Vec2,
+    editor_camera: Vec2,
+    playing: bool,
+    rerun: bool,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation {
+            seed: 1,
+            count: 200,
+            gap: 200.,
+            single_weights: (0..OBJECTS.len()).map(catalog::spawn_weight_of).collect(),
+            include_current: true,
+            sources: Vec::new(),
+            errors: Vec::new(),
+            spawns: Vec::new(),
+            origin: Vec2::ZERO,
+            editor_camera: Vec2::ZERO,
+            playing: false,
+            rerun: false,
+        }
+    }
+}
+
+pub struct SimulatePlugin;
+
+impl Plugin for SimulatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Simulation>()
+            .add_systems(OnEnter(GameState::Simulating), start_simulating)
+            .add_systems(
+                Update,
+                (run_simulation, simulation_window, show_stream)
+                    .chain()
+                    .run_if(in_state(GameState::Simulating)),
+            )
+            .add_systems(OnExit(GameState::Simulating), stop_simulating);
+    }
+}
+
+fn start_simulating(
+    mut sim: ResMut<Simulation>,
+    item_q: Query<(&Transform, &TextureAtlasSprite), With<BuiltItem>>,
+    mut camera_q: Query<&mut Transform, (With<Camera>, Without<BuiltItem>)>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut camera) = camera_q.get_single_mut() else {
+        return;
+    };
+    let height = q_windows.single().height();
+    let bottom = item_q
+        .iter()
+        .map(|(trans, sprite)| trans.translation.y - catalog::size_of(sprite.index).y / 2.)
+        .fold(camera.translation.y - height / 2., f32::min);
+    // The stream goes below the structure, so the two never overlap.
+    sim.editor_camera = camera.translation.truncate();
+    sim.origin = Vec2::new(0., bottom - height / 2.);
+    sim.playing = false;
+    sim.rerun = true;
+    camera.translation.x = 0.;
+    camera.translation.y = sim.origin.y - height * 0.4;
+}
+
+fn run_simulation(
+    mut sim: ResMut<Simulation>,
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    ui_state: Res<UiState>,
+    anchor: Res<Anchor>,
+    layers: Res<Layers>,
+) {
+    if !sim.rerun {
+        return;
+    }
+    sim.rerun = false;
+    let (mut sources, mut errors) = read_structures();
+    if sim.include_current {
+        let name = if ui_state.name.is_empty() {
+            "export"
+        } else {
+            &ui_state.name
+        };
+        match ui_state.weight_s.parse::<f64>() {
+            Ok(weight) => {
+                let items = exported_items(&item_q, &layers);
+                let origin = anchor.point(items.iter().map(|(_, p)| *p));
+                // Replaces the exported version, so a changed weight can be tried before exporting.
+                sources.retain(|source| source.name != name);
+                sources.push(SpawnSource {
+                    name: format!("{} (editing)", name),
+                    weight,
+                    items: items.into_iter().map(|(i, p)| (i, p - origin)).collect(),
+                    single: false,
+                });
+            }
+            Err(_) => errors.push(format!(
+                "{}: enter a valid weight to include the structure being edited",
+                name
+            )),
+        }
+    }
+    for (index, weight) in sim.single_weights.iter().enumerate() {
+        sources.push(SpawnSource {
+            name: catalog::name_of(index).to_string(),
+            weight: *weight,
+            items: vec![(index, Vec2::ZERO)],
+            single: true,
+        });
+    }
+    match simulate(&sources, sim.count, sim.seed, sim.gap) {
+        Ok(mut spawns) => {
+            for spawn in spawns.iter_mut() {
+                spawn.top += sim.origin.y;
+                spawn.bottom += sim.origin.y;
+                for (_, position) in spawn.items.iter_mut() {
+                    *position += sim.origin;
+                }
+            }
+            sim.spawns = spawns;
+        }
+        Err(e) => {
+            errors.push(e);
+            sim.spawns.clear();
+        }
+    }
+    sim.sources = sources;
+    sim.errors = errors;
+}
+
+/// A bar with the share of the whole next to the share the weights ask for.
+fn share_bar(ui: &mut egui::Ui, name: &str, count: usize, total: usize, expected: Option<f64>) {
+    let share = count as f32 / total.max(1) as f32;
+    let text = match expected {
+        Some(expected) => format!(
+            "{}: {} ({:.1}%, expected {:.1}%)",
+            name,
+            count,
+            share * 100.,
+            expected * 100.
+        ),
+        None => format!("{}: {} ({:.1}%)", name, count, share * 100.),
+    };
+    ui.add(egui::ProgressBar::new(share).text(text));
+}
+
+fn simulation_window(
+    mut sim: ResMut<Simulation>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+    keys: Res<Input<KeyCode>>,
+    mut next: ResMut<NextState<GameState>>,
+    mut contexts: EguiContexts,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next.set(GameState::Building);
+    }
+    let sim = &mut *sim;
+    egui::Window::new("Spawn simulator")
+        .default_pos(egui::pos2(15., 345.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Seed:");
+                ui.add(egui::DragValue::new(&mut sim.seed));
+                ui.label("Spawns:");
+                ui.add(egui::DragValue::new(&mut sim.count).clamp_range(1..=100000));
+                ui.label("Gap:");
+                ui.add(egui::DragValue::new(&mut sim.gap).clamp_range(0..=2000));
+            });
+            ui.checkbox(
+                &mut sim.include_current,
+                "Include the structure being edited",
+            );
+            ui.collapsing("Weights of single objects", |ui| {
+                for (index, weight) in sim.single_weights.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(catalog::name_of(index));
+                        ui.add(
+                            egui::DragValue::new(weight)
+                                .speed(0.1)
+                                .clamp_range(0..=100000),
+                        );
+                    });
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Run").clicked() {
+                    sim.rerun = true;
+                }
+                if ui.button("New seed").clicked() {
+                    sim.seed = rand::random::<u32>() as u64;
+                    sim.rerun = true;
+                }
+                let label = if sim.playing { "Pause" } else { "Scroll" };
+                if ui.button(label).clicked() {
+                    sim.playing = !sim.playing;
+                }
+                if ui.button("Stop").on_hover_text("Escape").clicked() {
+                    next.set(GameState::Building);
+                }
+            });
+            for error in sim.errors.iter() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            let total_weight: f64 = sim.sources.iter().map(|s| s.weight.max(0.)).sum();
+            let mut counts = vec![0; sim.sources.len()];
+            let mut objects = vec![0; OBJECTS.len()];
+            for spawn in sim.spawns.iter() {
+                counts[spawn.source] += 1;
+                for (index, _) in spawn.items.iter() {
+                    objects[*index] += 1;
+                }
+            }
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    ui.collapsing("By structure", |ui| {
+                        for (source, count) in sim.sources.iter().zip(counts.iter()) {
+                            let expected = source.weight.max(0.) / total_weight.max(f64::EPSILON);
+                            share_bar(ui, &source.name, *count, sim.spawns.len(), Some(expected));
+                        }
+                    });
+                    ui.collapsing("By object", |ui| {
+                        let total = objects.iter().sum();
+                        for (index, count) in objects.iter().enumerate() {
+                            share_bar(ui, catalog::name_of(index), *count, total, None);
+                        }
+                    });
+                    ui.collapsing("Sequence", |ui| {
+                        for (i, spawn) in sim.spawns.iter().enumerate() {
+                            let name = &sim.sources[spawn.source].name;
+                            if ui.link(format!("{}. {}", i + 1, name)).clicked() {
+                                sim.playing = false;
+                                for mut camera in camera_q.iter_mut() {
+                                    camera.translation.y = spawn.top;
+                                }
+                            }
+                        }
+                    });
+                });
+        });
+}
+
+fn show_stream(
+    time: Res<Time>,
+    settings: Res<PlaySettings>,
+    mut sim: ResMut<Simulation>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut preview: ResMut<GhostPreview>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut camera) = camera_q.get_single_mut() else {
+        return;
+    };
+    let height = q_windows.single().height();
+    if sim.playing {
+        camera.translation.y -= settings.fall_speed * time.delta_seconds();
+        let end = sim.spawns.last().map_or(sim.origin.y, |spawn| spawn.bottom);
+        if camera.translation.y < end - height / 2. {
+            sim.playing = false;
+        }
+    }
+    // Only what is on the screen, the stream can be far too long to show at once.
+    let (low, high) = (camera.translation.y - height, camera.translation.y + height);
+    let half = PLAY_COLUMN_WIDTH / 2.;
+    for spawn in sim.spawns.iter() {
+        if spawn.top < low {
+            break;
+        }
+        if spawn.bottom > high {
+            continue;
+        }
+        gizmos.line_2d(
+            Vec2::new(-half, spawn.top),
+            Vec2::new(half, spawn.top),
+            Color::rgba(1., 1., 1., 0.3),
+        );
+        preview.0.extend(
+            spawn
+                .items
+                .iter()
+                .filter(|(_, p)| p.y > low && p.y < high)
+                .copied(),
+        );
+    }
+}
+
+fn stop_simulating(sim: Res<Simulation>, mut camera_q: Query<&mut Transform, With<Camera>>) {
+    for mut camera in camera_q.iter_mut() {
+        camera.translation.x = sim.editor_camera.x;
+        camera.translation.y = sim.editor_camera.y;
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{
+    catalog,
+    ghost::GhostPreview,
+    grid::{snap_position, GridSettings, Guides},
+    mouse::{spawn_built_item, BuiltItem, DrawOrder, EditorTool, ItemName, ItemPlaced, Selected},
+    structure_ui::{overlaps_ui, ApplyDefaultColoring, PointerOverEgui, UISprite},
+    CombinedSheet, GameState,
+};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Next to the structures folder, the game never looks in here.
+pub const STAMPS_DIR: &str = "./stamps";
+pub const STAMP_EXTENSION: &str = "ron";
+
+/// Position relative to the middle of the stamp.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StampItem {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A group of objects that can be placed again and again. Items go from the back to the front.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Stamp {
+    pub name: String,
+    pub items: Vec<StampItem>,
+}
+
+impl Stamp {
+    pub fn read(path: &Path) -> Result<Stamp, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&text).map_err(|e| e.to_string())
+    }
+
+    pub fn write(&self) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(STAMPS_DIR).map_err(|e| e.to_string())?;
+        let path = Path::new(STAMPS_DIR).join(format!("{}.{}", self.name, STAMP_EXTENSION));
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(&path, text).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    /// Sprite indices and positions of the stamp placed with its middle at `centre`.
+    pub fn placed_at(&self, centre: Vec2) -> Vec<(usize, Vec2)> {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                let index = catalog::index_of(&item.name)?;
+                Some((index, centre + Vec2::new(item.x, item.y)))
+            })
+            .collect()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct StampPalette {
+    pub stamps: Vec<Stamp>,
+    /// Stamp placed by clicking on the canvas.
+    pub active: usize,
+    /// Name for the next stamp saved from the selection.
+    pub new_name: String,
+    pub status: String,
+}
+
+impl StampPalette {
+    fn refresh(&mut self) {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(STAMPS_DIR)
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|e| e == STAMP_EXTENSION))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        self.stamps = paths
+            .iter()
+            .filter_map(|path| Stamp::read(path).ok())
+            .collect();
+    }
+}
+
+pub struct StampPlugin;
+
+impl Plugin for StampPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StampPalette>()
+            .add_systems(Startup, |mut palette: ResMut<StampPalette>| {
+                palette.refresh()
+            })
+            .add_systems(Update, stamp_palette)
+            .add_systems(Update, place_stamp.run_if(in_state(GameState::Stamping)));
+    }
+}
+
+fn place_stamp(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    buttons: Res<Input<MouseButton>>,
+    editor_tool_q: Query<&Transform, With<EditorTool>>,
+    order_q: Query<&DrawOrder>,
+    ui_q: Query<(&Transform, &UISprite), With<UISprite>>,
+    over_egui: Res<PointerOverEgui>,
+    grid: Res<GridSettings>,
+    guides: Res<Guides>,
+    palette: Res<StampPalette>,
+    mut preview: ResMut<GhostPreview>,
+    mut placed: EventWriter<ItemPlaced>,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    let Some(stamp) = palette.stamps.get(palette.active) else {
+        return;
+    };
+    if overlaps_ui(tool, &ui_q) || over_egui.0 {
+        return;
+    }
+    let centre = snap_position(&grid, &guides, tool.translation.truncate());
+    let items = stamp.placed_at(centre);
+    if !buttons.just_pressed(MouseButton::Left) {
+        preview.0.extend(items);
+        return;
+    }
+    // The stamp is only one object while it is being placed, once down it is made of ordinary objects.
+    let first = DrawOrder::above(order_q.iter());
+    for (i, (index, position)) in items.into_iter().enumerate() {
+        let order = DrawOrder(first.0 + i as u32);
+        let entity = spawn_built_item(&mut commands, &texture_atlas.0, index, position, order);
+        placed.send(ItemPlaced {
+            entity,
+            index,
+            position,
+            order,
+        });
+    }
+}
+
+fn stamp_palette(
+    mut commands: Commands,
+    selected_q: Query<(&Transform, &ItemName, &DrawOrder), (With<Selected>, With<BuiltItem>)>,
+    selected_buttons: Query<Entity, (With<ApplyDefaultColoring>, With<Selected>)>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut palette: ResMut<StampPalette>,
+    state: Res<State<GameState>>,
+    mut next: ResMut<NextState<GameState>>,
+    mut contexts: EguiContexts,
+) {
+    let Ok(window) = q_windows.get_single() else {
+        return;
+    };
+    // Sits right above the object buttons, which take the bottom 15% of the window.
+    let offset = egui::vec2(-15., -(window.height() * 0.15 + 10.));
+    egui::Window::new("Stamps")
+        .anchor(egui::Align2::RIGHT_BOTTOM, offset)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let stamping = state.get() == &GameState::Stamping;
+            let palette = &mut *palette;
+            if palette.stamps.is_empty() {
+                ui.label("No stamps yet");
+            }
+            ui.horizontal_wrapped(|ui| {
+                for (i, stamp) in palette.stamps.iter().enumerate() {
+                    let active = stamping && palette.active == i;
+                    if ui.selectable_label(active, &stamp.name).clicked() {
+                        palette.active = i;
+                        for button in selected_buttons.iter() {
+                            commands.entity(button).remove::<Selected>();
+                        }
+                        next.set(GameState::Stamping);
+                    }
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut palette.new_name);
+                palette.new_name = palette.new_name.replace(['.', '/', '\\'], "");
+                let can_save = !selected_q.is_empty() && !palette.new_name.is_empty();
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save selection"))
+                    .on_hover_text("Saves the selected objects as a stamp with this name")
+                    .clicked()
+                {
+                    let mut items: Vec<(&Transform, &ItemName, &DrawOrder)> =
+                        selected_q.iter().collect();
+                    items.sort_by_key(|(_, _, order)| **order);
+                    let positions = items
+                        .iter()
+                        .map(|(trans, _, _)| trans.translation.truncate());
+                    let min = positions.clone().fold(Vec2::MAX, Vec2::min);
+                    let max = positions.fold(Vec2::MIN, Vec2::max);
+                    let centre = (min + max) / 2.;
+                    let stamp = Stamp {
+                        name: palette.new_name.clone(),
+                        items: items
+                            .iter()
+                            .map(|(trans, name, _)| StampItem {
+                                name: name.name.to_string(),
+                                x: trans.translation.x - centre.x,
+                                y: trans.translation.y - centre.y,
+                            })
+                            .collect(),
+                    };
+                    palette.status = match stamp.write() {
+                        Ok(path) => format!("Saved {}", path.display()),
+                        Err(e) => format!("Saving failed: {}", e),
+                    };
+                    palette.new_name.clear();
+                    palette.refresh();
+                }
+            });
+            if !palette.status.is_empty() {
+                ui.label(&palette.status);
+            }
+        });
+}
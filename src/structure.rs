@@ -0,0 +1,50 @@
+use std::path::Path;
+
+pub struct StructureItem {
+    pub x: f32,
+    pub y: f32,
+    pub name: String,
+}
+
+/// A structure in the format the game reads: weight on the first line, then "x y name" per object.
+pub struct StructureFile {
+    pub weight: String,
+    pub items: Vec<StructureItem>,
+}
+
+impl StructureFile {
+    pub fn read(path: &Path) -> Result<StructureFile, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut lines = text.lines();
+        let weight = lines.next().unwrap_or_default().trim().to_string();
+        if weight.parse::<f64>().is_err() {
+            return Err(format!("\"{}\" is not a valid weight", weight));
+        }
+        let mut items = Vec::new();
+        for (i, line) in lines.enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (Some(x), Some(y), Some(name)) = (parts.first(), parts.get(1), parts.get(2)) else {
+                return Err(format!("line {} is incomplete", i + 2));
+            };
+            let (Ok(x), Ok(y)) = (x.parse::<f32>(), y.parse::<f32>()) else {
+                return Err(format!("line {} has invalid coordinates", i + 2));
+            };
+            items.push(StructureItem {
+                x,
+                y,
+                name: name.to_string(),
+            });
+        }
+        Ok(StructureFile { weight, items })
+    }
+}
+
+/// Replaces the weight on the first line of an exported structure, leaving the objects as they are.
+pub fn write_weight(path: &Path, weight: &str) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let objects = text.split_once('\n').map_or("", |(_, rest)| rest);
+    std::fs::write(path, format!("{}\n{}", weight, objects)).map_err(|e| e.to_string())
+}
@@ -1,10 +1,15 @@
 use crate::{
-    export::SingleUse, mouse::EditorTool, CombinedSheet, EraserSheet, ExitSheet, GameState,
-    PrimaryWindow,
+    export::{ExportStructure, SingleUse},
+    mouse::EditorTool,
+    project::Dirty,
+    CombinedSheet, EraserSheet, ExitSheet, GameState, PrimaryWindow, UiState,
 };
 use bevy::app::AppExit;
+use bevy::ecs::query::ReadOnlyWorldQuery;
 use bevy::prelude::*;
 use bevy::sprite::collide_aabb::collide;
+use bevy::window::WindowCloseRequested;
+use bevy_egui::{egui, EguiContexts};
 
 #[derive(Component)]
 struct EditorButton {
@@ -28,41 +33,168 @@ pub struct UISprite {
     pub sprite_size: Vec2,
 }
 
+#[derive(Resource, Default)]
+pub struct PointerOverEgui(pub bool);
+
+#[derive(Resource, Default)]
+struct ExitConfirmation(bool);
+
 pub struct StructureUIPlugin;
 
 impl Plugin for StructureUIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Startup,
-            (spawn_main_buttons, spawn_eraser, vertical_bars, spawn_exit),
-        )
-        .add_systems(
-            Update,
-            (
-                update_freshly_unselected,
-                unselected_button_coloring,
-                selected_button_coloring,
-                eraser_button_interaction,
-                change_selection,
-                select_item,
-                exit_button_interaction,
-            ),
-        );
+        app.init_resource::<PointerOverEgui>()
+            .init_resource::<ExitConfirmation>()
+            .add_systems(
+                Startup,
+                (spawn_main_buttons, spawn_eraser, vertical_bars, spawn_exit),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_freshly_unselected,
+                    unselected_button_coloring,
+                    selected_button_coloring,
+                    eraser_button_interaction,
+                    change_selection,
+                    select_item,
+                    exit_button_interaction,
+                    exit_confirmation,
+                    tools_window,
+                    editor_tool_visibility.run_if(state_changed::<GameState>()),
+                    pointer_over_egui,
+                    follow_camera,
+                ),
+            );
+    }
+}
+
+fn tools_window(
+    mut commands: Commands,
+    mut next: ResMut<NextState<GameState>>,
+    state: Res<State<GameState>>,
+    selected_buttons: Query<Entity, (With<ApplyDefaultColoring>, With<Selected>)>,
+    mut contexts: EguiContexts,
+) {
+    egui::Window::new("Tools")
+        .default_pos(egui::pos2(15., 120.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for (tool, label) in [
+                    (GameState::Selecting, "Select"),
+                    (GameState::Patterning, "Pattern"),
+                    (GameState::Pathing, "Path"),
+                    (GameState::Playing, "Play"),
+                    (GameState::Simulating, "Simulate"),
+                    (GameState::Measuring, "Measure"),
+                ] {
+                    let active = state.get() == &tool;
+                    if ui.selectable_label(active, label).clicked() && !active {
+                        for button in selected_buttons.iter() {
+                            commands.entity(button).remove::<Selected>();
+                        }
+                        next.set(tool);
+                    }
+                }
+            });
+        });
+}
+
+/// The object following the cursor only makes sense while placing or erasing.
+fn editor_tool_visibility(
+    state: Res<State<GameState>>,
+    mut editor_tool_query: Query<&mut Visibility, With<EditorTool>>,
+) {
+    let visible = matches!(state.get(), GameState::Building | GameState::Erasing);
+    for mut visibility in editor_tool_query.iter_mut() {
+        *visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn pointer_over_egui(mut contexts: EguiContexts, mut over_egui: ResMut<PointerOverEgui>) {
+    let ctx = contexts.ctx_mut();
+    over_egui.0 = ctx.is_pointer_over_area() || ctx.wants_pointer_input();
+}
+
+/// UI sprites live in the world, so they have to move together with the camera to stay on screen.
+fn follow_camera(
+    camera_q: Query<&Transform, (With<Camera>, Without<UISprite>)>,
+    mut ui_q: Query<&mut Transform, With<UISprite>>,
+    mut last: Local<Vec2>,
+) {
+    let camera = camera_q.single().translation.truncate();
+    let delta = camera - *last;
+    if delta == Vec2::ZERO {
+        return;
+    }
+    for mut trans in ui_q.iter_mut() {
+        trans.translation += delta.extend(0.);
     }
+    *last = camera;
 }
 
 fn exit_button_interaction(
     eraser_button_q: Query<&Interaction, (Changed<Interaction>, With<ExitButton>)>,
+    mut close_requests: EventReader<WindowCloseRequested>,
     mut exit: EventWriter<AppExit>,
+    mut confirmation: ResMut<ExitConfirmation>,
+    dirty: Res<Dirty>,
 ) {
-    for interaction in eraser_button_q.iter() {
-        match *interaction {
-            Interaction::Pressed => {
-                exit.send(AppExit);
-            }
-            _ => {}
-        }
+    let pressed = eraser_button_q
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if !pressed && close_requests.iter().count() == 0 {
+        return;
     }
+    if dirty.dirty {
+        confirmation.0 = true;
+    } else {
+        exit.send(AppExit);
+    }
+}
+
+fn exit_confirmation(
+    mut confirmation: ResMut<ExitConfirmation>,
+    mut export: EventWriter<ExportStructure>,
+    mut exit: EventWriter<AppExit>,
+    ui_state: Res<UiState>,
+    mut contexts: EguiContexts,
+) {
+    if !confirmation.0 {
+        return;
+    }
+    egui::Window::new("Unexported changes")
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., 0.))
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("The structure has changes that were not exported or saved.");
+            if !ui_state.ready_to_export {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    "Enter a valid weight to be able to export.",
+                );
+            }
+            ui.horizontal(|ui| {
+                let export_button =
+                    ui.add_enabled(ui_state.ready_to_export, egui::Button::new("Export"));
+                if export_button.clicked() {
+                    export.send(ExportStructure { exit_after: true });
+                    confirmation.0 = false;
+                }
+                if ui.button("Discard").clicked() {
+                    exit.send(AppExit);
+                }
+                if ui.button("Cancel").clicked() {
+                    confirmation.0 = false;
+                }
+            });
+        });
 }
 
 fn spawn_exit(
@@ -134,9 +266,9 @@ fn eraser_button_interaction(
     }
 }
 
-pub fn overlaps_ui(
+pub fn overlaps_ui<F: ReadOnlyWorldQuery>(
     player_trans: &Transform,
-    ui_q: &Query<(&Transform, &UISprite), With<UISprite>>,
+    ui_q: &Query<(&Transform, &UISprite), F>,
 ) -> bool {
     let player_translation = player_trans.translation;
     for (trans, uisprite) in ui_q.iter() {
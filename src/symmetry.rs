@@ -0,0 +1,301 @@
+use crate::{
+    layers::Locked,
+    mouse::{spawn_built_item, BuiltItem, DrawOrder, ItemPlaced, Selected},
+    CombinedSheet,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::{collections::HashSet, f32::consts::TAU};
+
+/// Copies closer than this to another copy are not placed, like an object placed right on the axis.
+const SAME_SPOT: f32 = 1.;
+/// Copies already this close to where they belong are left alone, as rotating there and back is not exact.
+const FOLLOW_TOLERANCE: f32 = 0.01;
+const AXIS_LENGTH: f32 = 5000.;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SymmetryKind {
+    /// Mirrored left to right over a vertical axis.
+    Vertical,
+    /// Mirrored over both a vertical and a horizontal axis.
+    Both,
+    /// Rotated copies around the centre.
+    Radial,
+}
+
+#[derive(Clone, Copy)]
+pub struct Symmetry {
+    pub kind: SymmetryKind,
+    /// Where the axes cross, or the centre of rotation.
+    pub centre: Vec2,
+    pub copies: usize,
+}
+
+impl Symmetry {
+    /// Number of copies including the original.
+    fn slots(&self) -> usize {
+        match self.kind {
+            SymmetryKind::Vertical => 2,
+            SymmetryKind::Both => 4,
+            SymmetryKind::Radial => self.copies.max(2),
+        }
+    }
+
+    /// Where the original at `position` appears as the copy `slot`, slot 0 being the original itself.
+    fn apply(&self, slot: usize, position: Vec2) -> Vec2 {
+        let offset = position - self.centre;
+        let offset = match self.kind {
+            SymmetryKind::Vertical | SymmetryKind::Both => Vec2::new(
+                if slot & 1 == 1 { -offset.x } else { offset.x },
+                if slot & 2 == 2 { -offset.y } else { offset.y },
+            ),
+            SymmetryKind::Radial => {
+                Vec2::from_angle(TAU * slot as f32 / self.slots() as f32).rotate(offset)
+            }
+        };
+        self.centre + offset
+    }
+
+    /// Where the original is, given the position of the copy `slot`.
+    fn original(&self, slot: usize, position: Vec2) -> Vec2 {
+        match self.kind {
+            // Every flip undoes itself.
+            SymmetryKind::Vertical | SymmetryKind::Both => self.apply(slot, position),
+            SymmetryKind::Radial => self.apply((self.slots() - slot) % self.slots(), position),
+        }
+    }
+
+    /// Positions of all copies of an object, without the ones falling onto the same spot.
+    pub fn images(&self, position: Vec2) -> Vec<(usize, Vec2)> {
+        let mut images: Vec<(usize, Vec2)> = Vec::new();
+        for slot in 0..self.slots() {
+            let image = self.apply(slot, position);
+            if images
+                .iter()
+                .all(|(_, other)| other.distance(image) > SAME_SPOT)
+            {
+                images.push((slot, image));
+            }
+        }
+        images
+    }
+}
+
+#[derive(Resource)]
+pub struct SymmetrySettings {
+    pub enabled: bool,
+    pub symmetry: Symmetry,
+}
+
+impl Default for SymmetrySettings {
+    fn default() -> Self {
+        SymmetrySettings {
+            enabled: false,
+            symmetry: Symmetry {
+                kind: SymmetryKind::Vertical,
+                // The middle of the play column.
+                centre: Vec2::ZERO,
+                copies: 4,
+            },
+        }
+    }
+}
+
+/// Links the copies of one mirrored object, so moving or erasing one of them changes all of them.
+#[derive(Component)]
+pub struct Mirrored {
+    symmetry: Symmetry,
+    slot: usize,
+    partners: Vec<(Entity, usize)>,
+}
+
+pub struct SymmetryPlugin;
+
+impl Plugin for SymmetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SymmetrySettings>().add_systems(
+            Update,
+            (
+                symmetry_window,
+                mirror_placed,
+                follow_mirrors,
+                erase_mirrors,
+                draw_axes,
+            ),
+        );
+    }
+}
+
+fn mirror_placed(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    mut placed: EventReader<ItemPlaced>,
+    order_q: Query<&DrawOrder>,
+    settings: Res<SymmetrySettings>,
+) {
+    if !settings.enabled {
+        placed.clear();
+        return;
+    }
+    let placed: Vec<&ItemPlaced> = placed.iter().collect();
+    let mut next = DrawOrder::above(order_q.iter().chain(placed.iter().map(|p| &p.order)));
+    for item in placed {
+        let symmetry = settings.symmetry;
+        let mut members = vec![(item.entity, 0)];
+        for (slot, image) in symmetry.images(item.position).into_iter().skip(1) {
+            let copy = spawn_built_item(&mut commands, &texture_atlas.0, item.index, image, next);
+            next.0 += 1;
+            members.push((copy, slot));
+        }
+        for &(entity, slot) in members.iter() {
+            commands.entity(entity).insert(Mirrored {
+                symmetry,
+                slot,
+                partners: members.iter().filter(|m| m.0 != entity).copied().collect(),
+            });
+        }
+    }
+}
+
+fn follow_mirrors(
+    mut item_q: Query<(Entity, &mut Transform, &Mirrored), With<BuiltItem>>,
+    settings: Res<SymmetrySettings>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    // When several copies moved in the same frame, the first one decides where the others go.
+    let mut decided = HashSet::new();
+    let mut targets = Vec::new();
+    for (entity, trans, mirrored) in item_q.iter_mut() {
+        if !trans.is_changed() || decided.contains(&entity) {
+            continue;
+        }
+        let symmetry = mirrored.symmetry;
+        let original = symmetry.original(mirrored.slot, trans.translation.truncate());
+        decided.insert(entity);
+        for &(partner, slot) in mirrored.partners.iter() {
+            decided.insert(partner);
+            targets.push((partner, symmetry.apply(slot, original)));
+        }
+    }
+    for (partner, target) in targets {
+        if let Ok((_, mut trans, _)) = item_q.get_mut(partner) {
+            if trans.translation.truncate().distance(target) > FOLLOW_TOLERANCE {
+                trans.translation.x = target.x;
+                trans.translation.y = target.y;
+            }
+        }
+    }
+}
+
+/// Erases the remaining copies once one of them is gone, or forgets the link while symmetry is off
+/// or the copy is locked.
+fn erase_mirrors(
+    mut commands: Commands,
+    mut mirrored_q: Query<(Entity, &mut Mirrored, Option<&Locked>)>,
+    item_q: Query<Entity, With<BuiltItem>>,
+    settings: Res<SymmetrySettings>,
+) {
+    for (entity, mut mirrored, locked) in mirrored_q.iter_mut() {
+        let gone = mirrored
+            .partners
+            .iter()
+            .any(|(partner, _)| !item_q.contains(*partner));
+        if !gone {
+            continue;
+        }
+        if settings.enabled && locked.is_none() {
+            commands.entity(entity).despawn();
+        } else {
+            mirrored
+                .partners
+                .retain(|(partner, _)| item_q.contains(*partner));
+        }
+    }
+}
+
+fn draw_axes(settings: Res<SymmetrySettings>, mut gizmos: Gizmos) {
+    if !settings.enabled {
+        return;
+    }
+    let colour = Color::rgba(0.8, 0., 0.8, 0.6);
+    let symmetry = settings.symmetry;
+    let lines = match symmetry.kind {
+        SymmetryKind::Vertical => vec![Vec2::Y],
+        SymmetryKind::Both => vec![Vec2::Y, Vec2::X],
+        SymmetryKind::Radial => (0..symmetry.slots())
+            .map(|slot| Vec2::from_angle(TAU * slot as f32 / symmetry.slots() as f32))
+            .collect(),
+    };
+    for direction in lines {
+        let start = match symmetry.kind {
+            SymmetryKind::Radial => symmetry.centre,
+            _ => symmetry.centre - direction * AXIS_LENGTH,
+        };
+        gizmos.line_2d(start, symmetry.centre + direction * AXIS_LENGTH, colour);
+    }
+}
+
+fn symmetry_window(
+    mut commands: Commands,
+    texture_atlas: Res<CombinedSheet>,
+    selected_q: Query<(&Transform, &TextureAtlasSprite), (With<Selected>, With<BuiltItem>)>,
+    order_q: Query<&DrawOrder>,
+    mut settings: ResMut<SymmetrySettings>,
+    mut contexts: EguiContexts,
+) {
+    egui::Window::new("Symmetry")
+        .default_pos(egui::pos2(15., 300.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut settings.enabled, "Mirror placing, moving and erasing");
+            let symmetry = &mut settings.symmetry;
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut symmetry.kind, SymmetryKind::Vertical, "Vertical");
+                ui.selectable_value(&mut symmetry.kind, SymmetryKind::Both, "Both axes");
+                ui.selectable_value(&mut symmetry.kind, SymmetryKind::Radial, "Radial");
+            });
+            if symmetry.kind == SymmetryKind::Radial {
+                ui.horizontal(|ui| {
+                    ui.label("Copies:");
+                    ui.add(egui::DragValue::new(&mut symmetry.copies).clamp_range(2..=24));
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Centre x:");
+                ui.add(egui::DragValue::new(&mut symmetry.centre.x));
+                ui.label("y:");
+                ui.add(egui::DragValue::new(&mut symmetry.centre.y));
+            });
+            if ui.button("Play column centre").clicked() {
+                symmetry.centre.x = 0.;
+            }
+            ui.separator();
+            let symmetry = settings.symmetry;
+            if ui
+                .add_enabled(
+                    !selected_q.is_empty(),
+                    egui::Button::new("Mirror selection"),
+                )
+                .on_hover_text("Places mirrored copies of the selected objects once")
+                .clicked()
+            {
+                let mut order = DrawOrder::above(order_q.iter());
+                for (trans, sprite) in selected_q.iter() {
+                    let position = trans.translation.truncate();
+                    for (_, image) in symmetry.images(position).into_iter().skip(1) {
+                        spawn_built_item(
+                            &mut commands,
+                            &texture_atlas.0,
+                            sprite.index,
+                            image,
+                            order,
+                        );
+                        order.0 += 1;
+                    }
+                }
+            }
+        });
+}
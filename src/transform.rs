@@ -0,0 +1,310 @@
+use crate::{
+    mouse::{BuiltItem, EditorTool, Selected},
+    structure_ui::{overlaps_ui, PointerOverEgui, UISprite},
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+const HANDLE_RADIUS: f32 = 8.;
+/// Distance of the handles from the positions of the selected objects.
+const HANDLE_MARGIN: f32 = 40.;
+
+#[derive(Clone, Copy, PartialEq)]
+enum GizmoHandle {
+    Pivot,
+    Scale,
+    Rotate,
+}
+
+#[derive(Default)]
+enum TransformDrag {
+    #[default]
+    None,
+    Pivot,
+    /// Positions of the selection when the drag started, so it can be redone from scratch every frame.
+    Scale {
+        pivot: Vec2,
+        cursor: Vec2,
+        start: Vec<(Entity, Vec2)>,
+    },
+    Rotate {
+        pivot: Vec2,
+        cursor: Vec2,
+        start: Vec<(Entity, Vec2)>,
+    },
+}
+
+/// Scales, rotates and flips the positions of the selected objects, their sprites stay as they are.
+#[derive(Resource)]
+pub struct TransformGizmo {
+    /// Point chosen by the user, otherwise the middle of the selection is used.
+    pivot: Option<Vec2>,
+    handles: Vec<(GizmoHandle, Vec2)>,
+    drag: TransformDrag,
+    /// Values typed into the window, in percent and degrees.
+    scale: Vec2,
+    angle: f32,
+}
+
+impl Default for TransformGizmo {
+    fn default() -> Self {
+        TransformGizmo {
+            pivot: None,
+            handles: Vec::new(),
+            drag: TransformDrag::None,
+            scale: Vec2::splat(100.),
+            angle: 0.,
+        }
+    }
+}
+
+impl TransformGizmo {
+    pub fn grabs(&self, point: Vec2) -> bool {
+        self.handle_at(point).is_some()
+    }
+
+    fn handle_at(&self, point: Vec2) -> Option<GizmoHandle> {
+        self.handles
+            .iter()
+            .find(|(_, position)| position.distance(point) <= HANDLE_RADIUS)
+            .map(|(handle, _)| *handle)
+    }
+
+    fn pivot(&self, positions: &[(Entity, Vec2)]) -> Vec2 {
+        self.pivot
+            .unwrap_or_else(|| bounds(positions).map_or(Vec2::ZERO, |rect| rect.center()))
+    }
+}
+
+fn bounds(positions: &[(Entity, Vec2)]) -> Option<Rect> {
+    let (_, first) = positions.first()?;
+    Some(positions.iter().fold(
+        Rect::from_center_size(*first, Vec2::ZERO),
+        |rect, (_, p)| rect.union_point(*p),
+    ))
+}
+
+pub struct TransformPlugin;
+
+impl Plugin for TransformPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransformGizmo>()
+            .add_systems(
+                Update,
+                (transform_window, drag_handles)
+                    .chain()
+                    .run_if(in_state(GameState::Selecting)),
+            )
+            .add_systems(OnExit(GameState::Selecting), reset_gizmo);
+    }
+}
+
+fn set_positions(
+    selected_q: &mut Query<(Entity, &mut Transform), (With<Selected>, With<BuiltItem>)>,
+    positions: impl Iterator<Item = (Entity, Vec2)>,
+) {
+    for (entity, position) in positions {
+        if let Ok((_, mut trans)) = selected_q.get_mut(entity) {
+            trans.translation.x = position.x;
+            trans.translation.y = position.y;
+        }
+    }
+}
+
+fn drag_handles(
+    buttons: Res<Input<MouseButton>>,
+    editor_tool_q: Query<&Transform, (With<EditorTool>, Without<BuiltItem>)>,
+    mut selected_q: Query<(Entity, &mut Transform), (With<Selected>, With<BuiltItem>)>,
+    ui_q: Query<(&Transform, &UISprite), (With<UISprite>, Without<BuiltItem>)>,
+    over_egui: Res<PointerOverEgui>,
+    mut gizmo: ResMut<TransformGizmo>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(tool) = editor_tool_q.get_single() else {
+        return;
+    };
+    let cursor = tool.translation.truncate();
+    let positions: Vec<(Entity, Vec2)> = selected_q
+        .iter()
+        .map(|(entity, trans)| (entity, trans.translation.truncate()))
+        .collect();
+    let Some(rect) = bounds(&positions) else {
+        *gizmo = TransformGizmo {
+            scale: gizmo.scale,
+            angle: gizmo.angle,
+            ..Default::default()
+        };
+        return;
+    };
+
+    if !buttons.pressed(MouseButton::Left) {
+        gizmo.drag = TransformDrag::None;
+    }
+    match std::mem::take(&mut gizmo.drag) {
+        TransformDrag::None => {
+            if buttons.just_pressed(MouseButton::Left) && !overlaps_ui(tool, &ui_q) && !over_egui.0
+            {
+                let pivot = gizmo.pivot(&positions);
+                gizmo.drag = match gizmo.handle_at(cursor) {
+                    Some(GizmoHandle::Pivot) => TransformDrag::Pivot,
+                    Some(GizmoHandle::Scale) => TransformDrag::Scale {
+                        pivot,
+                        cursor,
+                        start: positions.clone(),
+                    },
+                    Some(GizmoHandle::Rotate) => TransformDrag::Rotate {
+                        pivot,
+                        cursor,
+                        start: positions.clone(),
+                    },
+                    None => TransformDrag::None,
+                };
+            }
+        }
+        TransformDrag::Pivot => {
+            gizmo.pivot = Some(cursor);
+            gizmo.drag = TransformDrag::Pivot;
+        }
+        TransformDrag::Scale {
+            pivot,
+            cursor: grab,
+            start,
+        } => {
+            let factor = pivot.distance(cursor) / pivot.distance(grab).max(1.);
+            set_positions(
+                &mut selected_q,
+                start
+                    .iter()
+                    .map(|(entity, p)| (*entity, pivot + (*p - pivot) * factor)),
+            );
+            gizmo.drag = TransformDrag::Scale {
+                pivot,
+                cursor: grab,
+                start,
+            };
+        }
+        TransformDrag::Rotate {
+            pivot,
+            cursor: grab,
+            start,
+        } => {
+            let rotation = Vec2::from_angle((grab - pivot).angle_between(cursor - pivot));
+            set_positions(
+                &mut selected_q,
+                start
+                    .iter()
+                    .map(|(entity, p)| (*entity, pivot + rotation.rotate(*p - pivot))),
+            );
+            gizmo.drag = TransformDrag::Rotate {
+                pivot,
+                cursor: grab,
+                start,
+            };
+        }
+    }
+
+    let pivot = match &gizmo.drag {
+        TransformDrag::Scale { pivot, .. } | TransformDrag::Rotate { pivot, .. } => *pivot,
+        _ => gizmo.pivot(&positions),
+    };
+    gizmo.handles = vec![
+        (GizmoHandle::Pivot, pivot),
+        (GizmoHandle::Scale, rect.max + Vec2::splat(HANDLE_MARGIN)),
+        (
+            GizmoHandle::Rotate,
+            Vec2::new(rect.center().x, rect.max.y + HANDLE_MARGIN * 2.),
+        ),
+    ];
+    let colour = Color::rgb(0.1, 0.7, 0.3);
+    gizmos.rect_2d(
+        rect.center(),
+        0.,
+        rect.size() + Vec2::splat(HANDLE_MARGIN * 2.),
+        colour,
+    );
+    for (handle, position) in gizmo.handles.iter() {
+        match handle {
+            GizmoHandle::Pivot => {
+                gizmos.circle_2d(*position, HANDLE_RADIUS, colour);
+                gizmos.circle_2d(*position, 2., colour);
+            }
+            GizmoHandle::Scale => {
+                gizmos.rect_2d(*position, 0., Vec2::splat(HANDLE_RADIUS * 2.), colour);
+            }
+            GizmoHandle::Rotate => {
+                gizmos.circle_2d(*position, HANDLE_RADIUS, colour);
+            }
+        }
+    }
+}
+
+fn transform_window(
+    mut selected_q: Query<(Entity, &mut Transform), (With<Selected>, With<BuiltItem>)>,
+    mut gizmo: ResMut<TransformGizmo>,
+    mut contexts: EguiContexts,
+) {
+    if selected_q.is_empty() {
+        return;
+    }
+    let positions: Vec<(Entity, Vec2)> = selected_q
+        .iter()
+        .map(|(entity, trans)| (entity, trans.translation.truncate()))
+        .collect();
+    let pivot = gizmo.pivot(&positions);
+    let mut transform: Option<Box<dyn Fn(Vec2) -> Vec2>> = None;
+    egui::Window::new("Transform")
+        .default_pos(egui::pos2(15., 470.))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Scale %:");
+                ui.add(egui::DragValue::new(&mut gizmo.scale.x).prefix("x "));
+                ui.add(egui::DragValue::new(&mut gizmo.scale.y).prefix("y "));
+                if ui.button("Apply").clicked() {
+                    let scale = gizmo.scale / 100.;
+                    transform = Some(Box::new(move |p| pivot + (p - pivot) * scale));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Rotate:");
+                ui.add(egui::DragValue::new(&mut gizmo.angle).suffix("°"));
+                if ui.button("Apply").clicked() {
+                    let rotation = Vec2::from_angle(gizmo.angle.to_radians());
+                    transform = Some(Box::new(move |p| pivot + rotation.rotate(p - pivot)));
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Flip horizontally").clicked() {
+                    transform = Some(Box::new(move |p| Vec2::new(2. * pivot.x - p.x, p.y)));
+                }
+                if ui.button("Flip vertically").clicked() {
+                    transform = Some(Box::new(move |p| Vec2::new(p.x, 2. * pivot.y - p.y)));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(format!("Pivot: {:.0}, {:.0}", pivot.x, pivot.y));
+                if ui
+                    .add_enabled(gizmo.pivot.is_some(), egui::Button::new("Reset"))
+                    .on_hover_text("Uses the middle of the selection again")
+                    .clicked()
+                {
+                    gizmo.pivot = None;
+                }
+            });
+        });
+    if let Some(transform) = transform {
+        set_positions(
+            &mut selected_q,
+            positions
+                .into_iter()
+                .map(|(entity, p)| (entity, transform(p))),
+        );
+    }
+}
+
+fn reset_gizmo(mut gizmo: ResMut<TransformGizmo>) {
+    gizmo.pivot = None;
+    gizmo.handles.clear();
+    gizmo.drag = TransformDrag::None;
+}
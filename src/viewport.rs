@@ -0,0 +1,169 @@
+use crate::{
+    catalog::{self, ObjectRole},
+    measure::{label_painter, paint_label},
+    mouse::{world_to_screen, BuiltItem},
+    play::{PlaySettings, PLAYER_SCREEN_Y},
+    GameState, GAME_HEIGHT, PLAY_COLUMN_WIDTH,
+};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+/// How far above the middle of the game's screen the player is.
+const PLAYER_OFFSET: f32 = GAME_HEIGHT * PLAYER_SCREEN_Y;
+const PLAYER_COLOUR: Color = Color::rgba(1., 0.4, 0.7, 0.9);
+const PLANE_COLOUR: Color = Color::rgba(0.2, 0.6, 1., 0.9);
+
+#[derive(Resource)]
+struct ViewportOverlay {
+    /// The game's screen and the player, following the camera.
+    screen: bool,
+    /// Where the player is when the structure comes into view and when it is gone again.
+    structure: bool,
+    planes: bool,
+}
+
+impl Default for ViewportOverlay {
+    fn default() -> Self {
+        ViewportOverlay {
+            screen: true,
+            structure: true,
+            planes: true,
+        }
+    }
+}
+
+pub struct ViewportPlugin;
+
+impl Plugin for ViewportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ViewportOverlay>()
+            .add_systems(Update, draw_viewport);
+    }
+}
+
+fn draw_viewport(
+    item_q: Query<(&Transform, &TextureAtlasSprite, &Visibility), With<BuiltItem>>,
+    camera_q: Query<&Transform, With<Camera>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<PlaySettings>,
+    state: Res<State<GameState>>,
+    mut overlay: ResMut<ViewportOverlay>,
+    mut gizmos: Gizmos,
+    mut contexts: EguiContexts,
+) {
+    let (Ok(camera), Ok(window)) = (camera_q.get_single(), q_windows.get_single()) else {
+        return;
+    };
+    egui::Window::new("Viewport")
+        .default_pos(egui::pos2(660., 285.))
+        .default_open(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut overlay.screen, "Game screen and player");
+            ui.checkbox(
+                &mut overlay.structure,
+                "Where the structure enters and leaves the screen",
+            );
+            ui.checkbox(&mut overlay.planes, "Where planes are released");
+            ui.label(format!(
+                "The game shows {:.0} x {:.0} px, the editor's canvas is {:.0} px tall.",
+                PLAY_COLUMN_WIDTH,
+                GAME_HEIGHT,
+                window.height() * 0.85
+            ));
+        });
+    // Playing and simulating show the real thing.
+    if matches!(state.get(), GameState::Playing | GameState::Simulating) {
+        return;
+    }
+
+    let half = PLAY_COLUMN_WIDTH / 2.;
+    let painter = label_painter(&mut contexts);
+    let label = |at: Vec2, text: String| {
+        paint_label(
+            &painter,
+            world_to_screen(window, camera, at + Vec2::new(10., 0.)),
+            text,
+        )
+    };
+    if overlay.screen {
+        let centre = camera.translation.truncate() * Vec2::Y;
+        let player = centre + Vec2::Y * PLAYER_OFFSET;
+        gizmos.rect_2d(
+            centre,
+            0.,
+            Vec2::new(PLAY_COLUMN_WIDTH, GAME_HEIGHT),
+            Color::rgba(1., 1., 1., 0.6),
+        );
+        gizmos.line_2d(
+            Vec2::new(-half, player.y),
+            Vec2::new(half, player.y),
+            PLAYER_COLOUR,
+        );
+        gizmos.rect_2d(player, 0., settings.hitbox, PLAYER_COLOUR);
+        label(Vec2::new(half, player.y), "Player".to_string());
+    }
+
+    let items: Vec<(usize, Vec2)> = item_q
+        .iter()
+        .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+        .map(|(trans, sprite, _)| (sprite.index, trans.translation.truncate()))
+        .collect();
+    let fall_speed = settings.fall_speed.max(1.);
+    if overlay.structure && !items.is_empty() {
+        let top = items
+            .iter()
+            .map(|(i, p)| p.y + catalog::size_of(*i).y / 2.)
+            .fold(f32::MIN, f32::max);
+        let bottom = items
+            .iter()
+            .map(|(i, p)| p.y - catalog::size_of(*i).y / 2.)
+            .fold(f32::MAX, f32::min);
+        // The top shows up at the bottom edge of the screen, the bottom disappears over its top edge.
+        let enters = top + GAME_HEIGHT / 2. + PLAYER_OFFSET;
+        let leaves = bottom - GAME_HEIGHT / 2. + PLAYER_OFFSET;
+        let colour = Color::rgba(1., 1., 0.3, 0.8);
+        for y in [enters, leaves] {
+            gizmos.line_2d(Vec2::new(-half, y), Vec2::new(half, y), colour);
+        }
+        label(
+            Vec2::new(half, enters),
+            format!(
+                "Player when the structure appears, {:.1} s before reaching it",
+                (enters - top - settings.hitbox.y / 2.) / fall_speed
+            ),
+        );
+        label(
+            Vec2::new(half, leaves),
+            format!(
+                "Player when the structure is gone, {:.1} s on screen",
+                (enters - leaves) / fall_speed
+            ),
+        );
+    }
+
+    if overlay.planes {
+        for (index, position) in items.iter() {
+            if catalog::role_of(*index) != ObjectRole::Sensor {
+                continue;
+            }
+            // Every position of the player's centre that touches the sensor.
+            let reach = catalog::size_of(*index) + settings.hitbox;
+            let trigger = position.y + reach.y / 2.;
+            gizmos.rect_2d(*position, 0., reach, PLANE_COLOUR);
+            gizmos.line_2d(
+                Vec2::new(position.x - reach.x / 2., trigger),
+                Vec2::new(position.x + reach.x / 2., trigger),
+                PLANE_COLOUR,
+            );
+            label(
+                Vec2::new(position.x + reach.x / 2., trigger),
+                format!(
+                    "Plane released with the player here, screen down to y {:.0}",
+                    trigger - PLAYER_OFFSET - GAME_HEIGHT / 2.
+                ),
+            );
+        }
+    }
+}